@@ -1,11 +1,13 @@
 use reqwest::Client;
 use serde_json::json;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, hash::Hash, instruction::Instruction,
-    message::AddressLookupTableAccount, native_token::sol_to_lamports, pubkey::Pubkey,
-    signature::Keypair, system_instruction,
+    commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction,
+    message::AddressLookupTableAccount, pubkey::Pubkey, signature::Signature, signer::Signer,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 
 use crate::*;
 
@@ -17,48 +19,29 @@ pub struct Astralane {
 }
 
 impl TransactionBuilder for Astralane {
-    fn build_v0_bs64(
+    fn build_v0_bs64<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String {
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String> {
         build_v0_bs64(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
     }
 
-    fn build_v0_bs58(
+    fn build_v0_bs58<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String {
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String> {
         build_v0_bs58(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
     }
-
-    fn simulate(
-        &self,
-        ixs: Vec<Instruction>,
-        fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
-        recent_blockhash: Hash,
-        nonce_ix: Option<Instruction>,
-        rpc_endpoint: String,
-    ) {
-        simulate(
-            ixs,
-            fee_payer,
-            signers,
-            recent_blockhash,
-            nonce_ix,
-            rpc_endpoint,
-        );
-    }
 }
 
 impl Astralane {
@@ -160,36 +143,26 @@ impl Astralane {
         // });
     }
 
-    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
-        let mut ixs: Vec<Instruction> = Vec::new();
-
-        if let Some(cu) = tip_config.cu {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cu as u32));
-        };
-
-        if let Some(priority_fee_micro_lamport) = tip_config.priority_fee_micro_lamport {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
-                priority_fee_micro_lamport,
-            ));
-        };
-
-        ixs.extend(tip_config.pure_ix.clone());
-
-        let relayer_fee = if self.endpoint.relayer == AstraRegionsType::Paladine {
-            tip_config.tip_sol_amount.max(ASTRA_PALADIN_MIN_TIP)
+    pub fn tip_ix(&self, tip_config: &Tips) -> Instruction {
+        let min_tip = if self.endpoint.relayer == AstraRegionsType::Paladine {
+            ASTRA_PALADIN_MIN_TIP
         } else {
-            tip_config.tip_sol_amount.max(ASTRA_IRIS_MIN_TIP)
+            ASTRA_IRIS_MIN_TIP
         };
 
-        let recipient = Pubkey::from_str_const(ASTRA_TIP[tip_config.tip_addr_idx as usize]);
-        let transfer_ix = system_instruction::transfer(
-            &tip_config.payer,
-            &recipient,
-            sol_to_lamports(relayer_fee),
-        );
-        ixs.push(transfer_ix);
+        tip_transfer_ix(
+            tip_config,
+            ASTRA_TIP[tip_config.tip_addr_idx as usize],
+            min_tip,
+        )
+    }
 
-        ixs
+    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
+        build_tip_ixs(&tip_config, self.tip_ix(&tip_config))
+    }
+
+    pub fn try_add_tip_ix(&self, tip_config: Tips) -> anyhow::Result<Vec<Instruction>> {
+        try_build_tip_ixs(&tip_config, self.tip_ix(&tip_config))
     }
 
     pub async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<serde_json::Value> {
@@ -211,6 +184,7 @@ impl Astralane {
             .json(&payload)
             .send()
             .await?;
+        check_rate_limit(&response)?;
 
         // ✅ Log the raw text before parsing
         let raw_text = response.text().await?;
@@ -229,4 +203,23 @@ impl Astralane {
 
         Ok(data)
     }
+
+    /// `send_transaction`, then with a tracker the wait for `signature` to reach `commitment`
+    pub async fn send_and_confirm(
+        &self,
+        encoded_tx: &str,
+        signature: Signature,
+        tracker: Option<&Arc<ConfirmationTracker>>,
+        commitment: CommitmentConfig,
+        last_valid_block_height: Option<u64>,
+    ) -> anyhow::Result<(serde_json::Value, Option<ConfirmationStatus>)> {
+        send_and_confirm(
+            self.send_transaction(encoded_tx),
+            signature,
+            tracker,
+            commitment,
+            last_valid_block_height,
+        )
+        .await
+    }
 }
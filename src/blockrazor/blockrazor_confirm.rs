@@ -4,11 +4,13 @@ use reqwest::{
 };
 use serde_json::json;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, hash::Hash, instruction::Instruction,
-    message::AddressLookupTableAccount, native_token::sol_to_lamports, pubkey::Pubkey,
-    signature::Keypair, system_instruction,
+    commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction,
+    message::AddressLookupTableAccount, pubkey::Pubkey, signature::Signature, signer::Signer,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 use crate::*;
@@ -21,48 +23,29 @@ pub struct BlockRazor {
 }
 
 impl TransactionBuilder for BlockRazor {
-    fn build_v0_bs64(
+    fn build_v0_bs64<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String {
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String> {
         build_v0_bs64(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
     }
 
-    fn build_v0_bs58(
+    fn build_v0_bs58<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String {
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String> {
         build_v0_bs58(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
     }
-
-    fn simulate(
-        &self,
-        ixs: Vec<Instruction>,
-        fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
-        recent_blockhash: Hash,
-        nonce_ix: Option<Instruction>,
-        rpc_endpoint: String,
-    ) {
-        simulate(
-            ixs,
-            fee_payer,
-            signers,
-            recent_blockhash,
-            nonce_ix,
-            rpc_endpoint,
-        );
-    }
 }
 
 impl BlockRazor {
@@ -177,32 +160,20 @@ impl BlockRazor {
         });
     }
 
-    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
-        let mut ixs: Vec<Instruction> = Vec::new();
-
-        if let Some(cu) = tip_config.cu {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cu as u32));
-        };
-
-        if let Some(priority_fee_micro_lamport) = tip_config.priority_fee_micro_lamport {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
-                priority_fee_micro_lamport,
-            ));
-        };
-
-        ixs.extend(tip_config.pure_ix.clone());
-
-        let relayer_fee = tip_config.tip_sol_amount.max(BLOCKRAZOR_MIN_TIP); // use `.max()` for clarity
+    pub fn tip_ix(&self, tip_config: &Tips) -> Instruction {
+        tip_transfer_ix(
+            tip_config,
+            BLOCKRAZOR_TIP[tip_config.tip_addr_idx as usize],
+            BLOCKRAZOR_MIN_TIP,
+        )
+    }
 
-        let recipient = Pubkey::from_str_const(BLOCKRAZOR_TIP[tip_config.tip_addr_idx as usize]);
-        let transfer_ix = system_instruction::transfer(
-            &tip_config.payer,
-            &recipient,
-            sol_to_lamports(relayer_fee),
-        );
-        ixs.push(transfer_ix);
+    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
+        build_tip_ixs(&tip_config, self.tip_ix(&tip_config))
+    }
 
-        ixs
+    pub fn try_add_tip_ix(&self, tip_config: Tips) -> anyhow::Result<Vec<Instruction>> {
+        try_build_tip_ixs(&tip_config, self.tip_ix(&tip_config))
     }
 
     pub async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<BlockRazorResponse> {
@@ -223,6 +194,7 @@ impl BlockRazor {
             .json(&payload)
             .send()
             .await?;
+        check_rate_limit(&response)?;
 
         let body = response.text().await?;
         println!("Raw response body:\n{}", body);
@@ -241,4 +213,23 @@ impl BlockRazor {
 
         Ok(response)
     }
+
+    /// `send_transaction`, then with a tracker the wait for `signature` to reach `commitment`
+    pub async fn send_and_confirm(
+        &self,
+        encoded_tx: &str,
+        signature: Signature,
+        tracker: Option<&Arc<ConfirmationTracker>>,
+        commitment: CommitmentConfig,
+        last_valid_block_height: Option<u64>,
+    ) -> anyhow::Result<(BlockRazorResponse, Option<ConfirmationStatus>)> {
+        send_and_confirm(
+            self.send_transaction(encoded_tx),
+            signature,
+            tracker,
+            commitment,
+            last_valid_block_height,
+        )
+        .await
+    }
 }
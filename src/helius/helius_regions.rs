@@ -73,3 +73,6 @@ pub const HELIUS_REGIONS: &[HeliusEndpoint] = &[
         "sender.helius-rpc.com"
     )
 ];
+
+/// Helius RPC (not Sender) endpoint, the api key is appended
+pub const HELIUS_RPC_ENDPOINT: &str = "https://mainnet.helius-rpc.com/?api-key=";
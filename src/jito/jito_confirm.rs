@@ -1,11 +1,13 @@
 use reqwest::Client;
 use serde_json::json;
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction, hash::Hash, instruction::Instruction,
-    message::AddressLookupTableAccount, native_token::sol_to_lamports, pubkey::Pubkey,
-    signature::Keypair, system_instruction,
+    commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction,
+    message::AddressLookupTableAccount, pubkey::Pubkey, signature::Signature, signer::Signer,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
 };
-use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
 use crate::*;
@@ -18,48 +20,29 @@ pub struct Jito {
 }
 
 impl TransactionBuilder for Jito {
-    fn build_v0_bs64(
+    fn build_v0_bs64<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String {
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String> {
         build_v0_bs64(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
     }
 
-    fn build_v0_bs58(
+    fn build_v0_bs58<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String {
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String> {
         build_v0_bs58(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
     }
-
-    fn simulate(
-        &self,
-        ixs: Vec<Instruction>,
-        fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
-        recent_blockhash: Hash,
-        nonce_ix: Option<Instruction>,
-        rpc_endpoint: String,
-    ) {
-        simulate(
-            ixs,
-            fee_payer,
-            signers,
-            recent_blockhash,
-            nonce_ix,
-            rpc_endpoint,
-        );
-    }
 }
 
 impl Jito {
@@ -187,32 +170,38 @@ impl Jito {
         // });
     }
 
-    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
-        let mut ixs: Vec<Instruction> = Vec::new();
-
-        if let Some(cu) = tip_config.cu {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(cu as u32));
-        };
-
-        if let Some(priority_fee_micro_lamport) = tip_config.priority_fee_micro_lamport {
-            ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
-                priority_fee_micro_lamport,
-            ));
-        };
-
-        ixs.extend(tip_config.pure_ix.clone());
+    pub fn tip_ix(&self, tip_config: &Tips) -> Instruction {
+        tip_transfer_ix(
+            tip_config,
+            JITO_TIP[tip_config.tip_addr_idx as usize],
+            JITO_MIN_TIP,
+        )
+    }
 
-        let relayer_fee = tip_config.tip_sol_amount.max(JITO_MIN_TIP); // use `.max()` for clarity
+    pub fn add_tip_ix(&self, tip_config: Tips) -> Vec<Instruction> {
+        build_tip_ixs(&tip_config, self.tip_ix(&tip_config))
+    }
 
-        let recipient = Pubkey::from_str_const(JITO_TIP[tip_config.tip_addr_idx as usize]);
-        let transfer_ix = system_instruction::transfer(
-            &tip_config.payer,
-            &recipient,
-            sol_to_lamports(relayer_fee),
-        );
-        ixs.push(transfer_ix);
+    pub fn try_add_tip_ix(&self, tip_config: Tips) -> anyhow::Result<Vec<Instruction>> {
+        try_build_tip_ixs(&tip_config, self.tip_ix(&tip_config))
+    }
 
-        ixs
+    /// Tip-only transaction paid by the tip source, to append to a bundle
+    /// built with `TipPlacement::Separate`
+    pub fn build_tip_tx_bs64<S: Signer + ?Sized>(
+        &self,
+        tip_config: &Tips,
+        signers: &[&S],
+        recent_blockhash: Hash,
+    ) -> anyhow::Result<String> {
+        build_v0_bs64(
+            vec![self.tip_ix(tip_config)],
+            &tip_config.tip_source(),
+            signers,
+            recent_blockhash,
+            None,
+            &[],
+        )
     }
 
     pub async fn send_transaction(&self, encoded_tx: &str) -> anyhow::Result<JsonRpcResponse> {
@@ -241,6 +230,7 @@ impl Jito {
         }
 
         let response = req.send().await?;
+        check_rate_limit(&response)?;
         let body = response.text().await?;
         println!("Raw response body:\n{:#?}", body);
 
@@ -255,6 +245,26 @@ impl Jito {
         Ok(response)
     }
 
+    /// `send_transaction`, then with a tracker the wait for `signature` to reach `commitment`
+    pub async fn send_and_confirm(
+        &self,
+        encoded_tx: &str,
+        signature: Signature,
+        tracker: Option<&Arc<ConfirmationTracker>>,
+        commitment: CommitmentConfig,
+        last_valid_block_height: Option<u64>,
+    ) -> anyhow::Result<(JsonRpcResponse, Option<ConfirmationStatus>)> {
+        send_and_confirm(
+            self.send_transaction(encoded_tx),
+            signature,
+            tracker,
+            commitment,
+            last_valid_block_height,
+        )
+        .await
+    }
+
+    /// Base64 transactions, as built by `build_v0_bs64` and `build_tip_tx_bs64`
     pub async fn send_bundle(&self, encoded_txs: &[String]) -> anyhow::Result<JsonRpcResponse> {
         let start = Instant::now();
 
@@ -273,7 +283,7 @@ impl Jito {
             "jsonrpc": "2.0",
             "id": 1,
             "method": "sendBundle",
-            "params": [encoded_txs]
+            "params": [encoded_txs, {"encoding": "base64"}]
         });
 
         let mut req = self.client.post(url).json(&payload);
@@ -283,6 +293,7 @@ impl Jito {
         }
 
         let response = req.send().await?;
+        check_rate_limit(&response)?;
         let body = response.text().await?;
         println!("Raw response body:\n{}", body);
 
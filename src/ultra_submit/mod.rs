@@ -1,19 +1,51 @@
+pub mod ultra_submit_attribution;
+pub mod ultra_submit_breaker;
+pub mod ultra_submit_completion;
+pub mod ultra_submit_cost;
+pub mod ultra_submit_escalation;
+pub mod ultra_submit_rate_limit;
+pub mod ultra_submit_routing;
+pub mod ultra_submit_schedule;
+pub mod ultra_submit_stats;
+#[cfg(test)]
+mod ultra_submit_test_support;
+pub use ultra_submit_attribution::*;
+pub use ultra_submit_breaker::*;
+pub use ultra_submit_completion::*;
+pub use ultra_submit_cost::*;
+pub use ultra_submit_escalation::*;
+pub use ultra_submit_rate_limit::*;
+pub use ultra_submit_routing::*;
+pub use ultra_submit_schedule::*;
+pub use ultra_submit_stats::*;
+
 use crate::*;
 use solana_sdk::message::AddressLookupTableAccount;
-use solana_sdk::{hash::Hash, instruction::Instruction, signature::Keypair};
-use std::collections::HashMap;
+use solana_sdk::{hash::Hash, instruction::Instruction, signature::Signature};
+use std::collections::{HashMap, HashSet, hash_map::Entry};
 use std::sync::Arc;
 use std::time::Instant;
-use futures::future::join_all;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
+use tokio::sync::watch;
+use tokio::time::sleep;
 
 /// Submission result tracking
 #[derive(Debug, Clone)]
 pub struct SubmissionResult {
     pub service_name: String,
+    /// Endpoint name, e.g. `Jito-Amsterdam`
+    pub region: String,
     pub attempt: u32,
     pub success: bool,
+    /// Network round trip only, signing is reported in `build_ms`
     pub latency_ms: f64,
+    pub build_ms: f64,
+    /// Signature of the transaction sent, `None` when rejected before building
+    pub signature: Option<Signature>,
     pub error: Option<String>,
+    /// The relayer answered 429
+    pub rate_limited: Option<RateLimited>,
 }
 
 /// Service configuration for submission
@@ -26,138 +58,263 @@ pub struct ServiceConfig {
 /// Enum for different service clients
 #[derive(Debug, Clone)]
 pub enum ServiceClient {
-    Jito(&'static Jito),
-    LilJit(&'static Jito),
-    Astralane(&'static Astralane),
-    Helius(&'static Helius),
-    NextBlock(&'static NextBlock),
-    ZeroSlot(&'static ZeroSlot),
-    Nozomi(&'static Nozomi),
-    BlockRazor(&'static BlockRazor),
-    BloxRoute(&'static BloxRoute),
+    Jito(Arc<Jito>),
+    LilJit(Arc<Jito>),
+    Astralane(Arc<Astralane>),
+    Helius(Arc<Helius>),
+    NextBlock(Arc<NextBlock>),
+    ZeroSlot(Arc<ZeroSlot>),
+    Nozomi(Arc<Nozomi>),
+    BlockRazor(Arc<BlockRazor>),
+    BloxRoute(Arc<BloxRoute>),
+}
+
+/// Optional behaviour for ultra_submit
+#[derive(Debug, Clone, Default)]
+pub struct UltraSubmitOptions {
+    pub escalation: Option<TipEscalation>,
+    pub budget: Option<Arc<BudgetGuard>>,
+    /// Refuse or rebuild when `recent_blockhash` is close to expiry
+    pub blockhash: Option<BlockhashPolicy>,
+    pub schedule: SubmissionSchedule,
+    pub completion: CompletionPolicy,
+    pub remainder: RemainderPolicy,
+    /// Receives every result as it arrives, including ones finishing after an early return
+    pub results: Option<UnboundedSender<SubmissionResult>>,
+    /// Aggregates every result and landing, late results included
+    pub stats: Option<Arc<RelayerStats>>,
+    /// Narrows the given relayers to the ones worth sending to
+    pub routing: Option<Arc<RoutingPolicy>>,
+    /// Skips relayers whose breaker is open and feeds it every result
+    pub breakers: Option<Arc<CircuitBreakers>>,
+    /// Token buckets per relayer API key, every send takes a token
+    pub rate_limits: Option<Arc<RateLimiters>>,
+}
+
+impl ServiceClient {
+    /// Minimum tip enforced by this client's `add_tip_ix`
+    pub fn min_tip(&self) -> f64 {
+        match self {
+            ServiceClient::Jito(_) | ServiceClient::LilJit(_) => JITO_MIN_TIP,
+            ServiceClient::Astralane(client) => {
+                if client.endpoint.relayer == AstraRegionsType::Paladine {
+                    ASTRA_PALADIN_MIN_TIP
+                } else {
+                    ASTRA_IRIS_MIN_TIP
+                }
+            }
+            ServiceClient::Helius(_) => HELIUS_MIN_TIP,
+            ServiceClient::NextBlock(_) => NEXTBLOCK_MIN_TIP,
+            ServiceClient::ZeroSlot(_) => ZSLOT_MIN_TIP,
+            ServiceClient::Nozomi(_) => NOZOMI_MIN_TIP,
+            ServiceClient::BlockRazor(_) => BLOCKRAZOR_MIN_TIP,
+            ServiceClient::BloxRoute(_) => BLOXROUTE_MIN_TIP,
+        }
+    }
+
+    /// API key quotas are enforced against, `None` for keyless Jito
+    pub fn api_key(&self) -> Option<&str> {
+        match self {
+            ServiceClient::Jito(client) | ServiceClient::LilJit(client) => {
+                client.auth_key.as_deref()
+            }
+            ServiceClient::Astralane(client) => Some(&client.auth_key),
+            ServiceClient::Helius(client) => Some(&client.auth_key),
+            ServiceClient::NextBlock(client) => Some(&client.auth_key),
+            ServiceClient::ZeroSlot(client) => Some(&client.auth_key),
+            ServiceClient::Nozomi(client) => Some(&client.auth_key),
+            ServiceClient::BlockRazor(client) => Some(&client.auth_key),
+            ServiceClient::BloxRoute(client) => Some(&client.auth_key),
+        }
+    }
+
+    /// Submit URL and HTTP client, used to probe the relayer without sending a transaction
+    pub fn http_endpoint(&self) -> (&reqwest::Client, &'static str) {
+        match self {
+            ServiceClient::Jito(client) | ServiceClient::LilJit(client) => {
+                (&client.client, client.endpoint.submit_endpoint)
+            }
+            ServiceClient::Astralane(client) => (&client.client, client.endpoint.submit_endpoint),
+            ServiceClient::Helius(client) => (&client.client, client.endpoint.submit_endpoint),
+            ServiceClient::NextBlock(client) => (&client.client, client.endpoint.submit_endpoint),
+            ServiceClient::ZeroSlot(client) => (&client.client, client.endpoint.submit_endpoint),
+            ServiceClient::Nozomi(client) => (&client.client, client.endpoint.submit_endpoint),
+            ServiceClient::BlockRazor(client) => (&client.client, client.endpoint.submit_endpoint),
+            ServiceClient::BloxRoute(client) => (&client.client, client.endpoint.submit_endpoint),
+        }
+    }
+
+    /// Endpoint name of the client, e.g. `Jito-Amsterdam`
+    pub fn region(&self) -> &'static str {
+        match self {
+            ServiceClient::Jito(client) | ServiceClient::LilJit(client) => {
+                client.endpoint.relayer_name
+            }
+            ServiceClient::Astralane(client) => client.endpoint.relayer_name,
+            ServiceClient::Helius(client) => client.endpoint.relayer_name,
+            ServiceClient::NextBlock(client) => client.endpoint.relayer_name,
+            ServiceClient::ZeroSlot(client) => client.endpoint.relayer_name,
+            ServiceClient::Nozomi(client) => client.endpoint.relayer_name,
+            ServiceClient::BlockRazor(client) => client.endpoint.relayer_name,
+            ServiceClient::BloxRoute(client) => client.endpoint.relayer_name,
+        }
+    }
+}
+
+fn rejection(
+    service: &ServiceConfig,
+    attempt: u32,
+    source: &str,
+    err: &dyn std::fmt::Display,
+) -> SubmissionResult {
+    eprintln!("[{} #{}] 🛑 Rejected by {}: {}", service.name, attempt + 1, source, err);
+
+    SubmissionResult {
+        service_name: service.name.to_string(),
+        region: service.client.region().to_string(),
+        attempt,
+        success: false,
+        latency_ms: 0.0,
+        build_ms: 0.0,
+        signature: None,
+        error: Some(err.to_string()),
+        rate_limited: None,
+    }
+}
+
+/// Adds the relayer's tip and compiles and signs its transaction
+fn build_for_service(
+    config: &ServiceConfig,
+    tx_info: &Tips,
+    signers: &[&DynSigner],
+    recent_blockhash: Hash,
+    nonce_ix: &Instruction,
+    alt: &[AddressLookupTableAccount],
+) -> anyhow::Result<BuiltTransaction> {
+    let ixs = match &config.client {
+        ServiceClient::Jito(client) | ServiceClient::LilJit(client) => {
+            client.try_add_tip_ix(tx_info.clone())?
+        }
+        ServiceClient::Astralane(client) => client.try_add_tip_ix(tx_info.clone())?,
+        ServiceClient::Helius(client) => client.try_add_tip_ix(tx_info.clone(), false)?,
+        ServiceClient::NextBlock(client) => client.try_add_tip_ix(tx_info.clone())?,
+        ServiceClient::ZeroSlot(client) => client.try_add_tip_ix(tx_info.clone())?,
+        ServiceClient::Nozomi(client) => client.try_add_tip_ix(tx_info.clone())?,
+        ServiceClient::BlockRazor(client) => client.try_add_tip_ix(tx_info.clone())?,
+        ServiceClient::BloxRoute(client) => client.try_add_tip_ix(tx_info.clone())?,
+    };
+
+    build_v0(
+        ixs,
+        &tx_info.payer,
+        signers,
+        recent_blockhash,
+        Some(nonce_ix.clone()),
+        alt,
+    )
+}
+
+/// Transactions built per (service, region, attempt): regions of one service sign separately
+type BuiltTransactions = HashMap<(&'static str, &'static str, u32), (Arc<BuiltTransaction>, f64)>;
+/// A (service, attempt) ready to send with its transaction and build time
+type ReadySubmission = (ServiceConfig, u32, Arc<BuiltTransaction>, f64);
+
+/// Builds and signs every planned submission. Relayers differ by tip account; without
+/// escalation all attempts of a relayer reuse the same bytes.
+fn build_planned(
+    planned: Vec<(ServiceConfig, u32, Tips, u64)>,
+    escalates: bool,
+    signers: &[Arc<DynSigner>],
+    recent_blockhash: Hash,
+    nonce_ix: &Instruction,
+    alt: &[AddressLookupTableAccount],
+    ledger: &SubmissionLedger,
+) -> (
+    BuiltTransactions,
+    Vec<ReadySubmission>,
+    Vec<SubmissionResult>,
+) {
+    let signer_refs: Vec<&DynSigner> = signers.iter().map(|signer| signer.as_ref()).collect();
+    let mut built_txs = BuiltTransactions::new();
+    let mut ready = Vec::new();
+    let mut rejected = Vec::new();
+
+    for (service_config, attempt, attempt_tx_info, fee_lamports) in planned {
+        let build_attempt = if escalates { attempt } else { 0 };
+        let key = (service_config.name, service_config.client.region(), build_attempt);
+
+        if let Entry::Vacant(entry) = built_txs.entry(key) {
+            let tx_build_start = Instant::now();
+            match build_for_service(
+                &service_config,
+                &attempt_tx_info,
+                &signer_refs,
+                recent_blockhash,
+                nonce_ix,
+                alt,
+            ) {
+                Ok(built) => {
+                    let build_ms = tx_build_start.elapsed().as_secs_f64() * 1000.0;
+                    ledger.record(
+                        built.signature,
+                        SubmissionRecord {
+                            service_name: service_config.name,
+                            region: service_config.client.region(),
+                            attempt: build_attempt,
+                            fee_lamports,
+                            first_sent_at: None,
+                        },
+                    );
+                    entry.insert((Arc::new(built), build_ms));
+                }
+                Err(err) => {
+                    rejected.push(rejection(&service_config, attempt, "transaction build", &err));
+                    continue;
+                }
+            }
+        }
+
+        let (built, build_ms) = built_txs[&key].clone();
+        ready.push((service_config, attempt, built, build_ms));
+    }
+
+    (built_txs, ready, rejected)
 }
 
-/// Generic submission function to eliminate code duplication
+/// Sends a pre-built transaction to one relayer
 async fn submit_to_service(
     config: ServiceConfig,
-    tx_info: Tips,
-    signers: &'static Vec<&'static Keypair>,
-    recent_blockhash: Hash,
-    nonce_ix: Instruction,
-    alt: Vec<AddressLookupTableAccount>,
+    built: Arc<BuiltTransaction>,
+    build_ms: f64,
     attempt: u32,
 ) -> SubmissionResult {
     let start = Instant::now();
     let service_name = config.name.to_string();
-    
+    let region = config.client.region().to_string();
+    let tx = built.base64();
+
     let result: anyhow::Result<serde_json::Value> = match config.client {
-        ServiceClient::Jito(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
-        }
-        ServiceClient::LilJit(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
-        }
-        ServiceClient::Astralane(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await
+        ServiceClient::Jito(client) | ServiceClient::LilJit(client) => {
+            client.send_transaction(tx).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
+        ServiceClient::Astralane(client) => client.send_transaction(tx).await,
         ServiceClient::Helius(client) => {
-            let ix = client.add_tip_ix(tx_info.clone(), false);
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
+            client.send_transaction(tx).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
         ServiceClient::NextBlock(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx, None).await.map(|resp| serde_json::to_value(resp).unwrap())
+            client.send_transaction(tx, None).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
         ServiceClient::ZeroSlot(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
+            client.send_transaction(tx).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
         ServiceClient::Nozomi(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
+            client.send_transaction(tx).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
         ServiceClient::BlockRazor(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
+            client.send_transaction(tx).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
         ServiceClient::BloxRoute(client) => {
-            let ix = client.add_tip_ix(tx_info.clone());
-            let tx = client.build_v0_bs64(
-                ix,
-                &tx_info.payer,
-                signers,
-                recent_blockhash,
-                Some(nonce_ix.clone()),
-                alt.clone()
-            );
-            client.send_transaction(&tx).await.map(|resp| serde_json::to_value(resp).unwrap())
+            client.send_transaction(tx).await.map(|resp| serde_json::to_value(resp).unwrap())
         }
     };
 
@@ -171,14 +328,19 @@ async fn submit_to_service(
             );
             SubmissionResult {
                 service_name,
+                region,
                 attempt,
                 success: true,
                 latency_ms,
+                build_ms,
+                signature: Some(built.signature),
                 error: None,
+                rate_limited: None,
             }
         }
         Err(e) => {
             let error_msg = format!("{}", e);
+            let rate_limited = as_rate_limited(&e);
             
             // Enhanced error reporting with detailed analysis
             eprintln!(
@@ -197,107 +359,248 @@ async fn submit_to_service(
                 eprintln!("   💡 Authentication failed - verify API key is valid");
             } else if error_msg.contains("not authorised") {
                 eprintln!("   💡 API key may be invalid or expired");
+            } else if rate_limited.is_some() {
+                eprintln!("   💡 Request quota exceeded - lower the rate or configure a rate limiter");
             }
 
             SubmissionResult {
                 service_name,
+                region,
                 attempt,
                 success: false,
                 latency_ms,
+                build_ms,
+                signature: Some(built.signature),
                 error: Some(error_msg),
+                rate_limited,
             }
         }
     }
 }
 
+/// Relayer clients ultra_submit fans out to, plus its optional behaviour
+#[derive(Debug, Clone, Default)]
+pub struct UltraSubmitConfig {
+    pub jito: Option<Arc<Jito>>,
+    pub liljit: Option<Arc<Jito>>,
+    pub astralane: Option<Arc<Astralane>>,
+    pub helius: Option<Arc<Helius>>,
+    pub nextblock: Option<Arc<NextBlock>>,
+    pub zeroslot: Option<Arc<ZeroSlot>>,
+    pub nozomi: Option<Arc<Nozomi>>,
+    pub blockrazor: Option<Arc<BlockRazor>>,
+    pub bloxroute: Option<Arc<BloxRoute>>,
+    pub options: UltraSubmitOptions,
+}
+
+impl UltraSubmitConfig {
+    /// Every configured client, in submission order
+    pub fn services(&self) -> Vec<ServiceConfig> {
+        let mut services = Vec::new();
+
+        if let Some(client) = &self.jito {
+            services.push(ServiceConfig {
+                name: "Jito",
+                client: ServiceClient::Jito(client.clone()),
+            });
+        }
+        if let Some(client) = &self.liljit {
+            services.push(ServiceConfig {
+                name: "LilJit",
+                client: ServiceClient::LilJit(client.clone()),
+            });
+        }
+        if let Some(client) = &self.astralane {
+            services.push(ServiceConfig {
+                name: "Astralane",
+                client: ServiceClient::Astralane(client.clone()),
+            });
+        }
+        if let Some(client) = &self.helius {
+            services.push(ServiceConfig {
+                name: "Helius",
+                client: ServiceClient::Helius(client.clone()),
+            });
+        }
+        if let Some(client) = &self.nextblock {
+            services.push(ServiceConfig {
+                name: "NextBlock",
+                client: ServiceClient::NextBlock(client.clone()),
+            });
+        }
+        if let Some(client) = &self.zeroslot {
+            services.push(ServiceConfig {
+                name: "ZeroSlot",
+                client: ServiceClient::ZeroSlot(client.clone()),
+            });
+        }
+        if let Some(client) = &self.nozomi {
+            services.push(ServiceConfig {
+                name: "Nozomi",
+                client: ServiceClient::Nozomi(client.clone()),
+            });
+        }
+        if let Some(client) = &self.blockrazor {
+            services.push(ServiceConfig {
+                name: "BlockRazor",
+                client: ServiceClient::BlockRazor(client.clone()),
+            });
+        }
+        if let Some(client) = &self.bloxroute {
+            services.push(ServiceConfig {
+                name: "BloxRoute",
+                client: ServiceClient::BloxRoute(client.clone()),
+            });
+        }
+
+        services
+    }
+}
+
 pub async fn ultra_submit(
     tx_info: Tips,
-    signers: &'static Vec<&'static Keypair>,
+    signers: Arc<[Arc<DynSigner>]>,
     recent_blockhash: Hash,
     nonce_ix: Instruction,
-    alt: Vec<AddressLookupTableAccount>,
+    alt: Arc<[AddressLookupTableAccount]>,
     retry_count: u32,
-    jito: Option<&'static Jito>,
-    liljit: Option<&'static Jito>,
-    astralance: Option<&'static Astralane>,
-    helius: Option<&'static Helius>,
-    nextblock: Option<&'static NextBlock>,
-    zeroslot: Option<&'static ZeroSlot>,
-    nozomi: Option<&'static Nozomi>,
-    blockrazor: Option<&'static BlockRazor>,
-    bloxroute: Option<&'static BloxRoute>,
-) {
+    config: UltraSubmitConfig,
+) -> UltraSubmitOutcome {
     let global_start = Instant::now();
     println!("🚀 Starting ultra_submit process with optimized multi-threading...");
 
     // Collect all available services
-    let mut services = Vec::new();
-    
-    if let Some(client) = jito {
-        services.push(ServiceConfig {
-            name: "Jito",
-            client: ServiceClient::Jito(client),
-        });
-    }
-    
-    if let Some(client) = liljit {
-        services.push(ServiceConfig {
-            name: "LilJit",
-            client: ServiceClient::LilJit(client),
-        });
-    }
-    
-    if let Some(client) = astralance {
-        services.push(ServiceConfig {
-            name: "Astralane",
-            client: ServiceClient::Astralane(client),
-        });
-    }
-    
-    if let Some(client) = helius {
-        services.push(ServiceConfig {
-            name: "Helius",
-            client: ServiceClient::Helius(client),
-        });
-    }
-    
-    if let Some(client) = nextblock {
-        services.push(ServiceConfig {
-            name: "NextBlock",
-            client: ServiceClient::NextBlock(client),
-        });
-    }
-    
-    if let Some(client) = zeroslot {
-        services.push(ServiceConfig {
-            name: "ZeroSlot",
-            client: ServiceClient::ZeroSlot(client),
-        });
-    }
-    
-    if let Some(client) = nozomi {
-        services.push(ServiceConfig {
-            name: "Nozomi",
-            client: ServiceClient::Nozomi(client),
-        });
-    }
-    
-    if let Some(client) = blockrazor {
-        services.push(ServiceConfig {
-            name: "BlockRazor",
-            client: ServiceClient::BlockRazor(client),
-        });
-    }
-    
-    if let Some(client) = bloxroute {
-        services.push(ServiceConfig {
-            name: "BloxRoute",
-            client: ServiceClient::BloxRoute(client),
-        });
-    }
+    let services = config.services();
+
+    launch_submissions(
+        global_start,
+        services,
+        tx_info,
+        signers,
+        recent_blockhash,
+        nonce_ix,
+        alt,
+        retry_count,
+        config.options,
+    )
+    .await
+}
+
+/// Convenience function for ultra_submit with simplified parameters
+/// This function provides a cleaner interface for common use cases
+#[allow(clippy::too_many_arguments)]
+pub async fn ultra_submit_simple(
+    tx_info: Tips,
+    signers: Arc<[Arc<DynSigner>]>,
+    recent_blockhash: Hash,
+    nonce_ix: Instruction,
+    alt: Arc<[AddressLookupTableAccount]>,
+    retry_count: u32,
+    services: Vec<ServiceConfig>,
+    options: UltraSubmitOptions,
+) -> UltraSubmitOutcome {
+    let global_start = Instant::now();
+    println!("🚀 Starting ultra_submit_simple with {} services", services.len());
+
+    launch_submissions(
+        global_start,
+        services,
+        tx_info,
+        signers,
+        recent_blockhash,
+        nonce_ix,
+        alt,
+        retry_count,
+        options,
+    )
+    .await
+}
 
+/// Spawns every (service, attempt) submission, waits per the completion policy and prints the stats
+#[allow(clippy::too_many_arguments)]
+async fn launch_submissions(
+    global_start: Instant,
+    services: Vec<ServiceConfig>,
+    tx_info: Tips,
+    signers: Arc<[Arc<DynSigner>]>,
+    recent_blockhash: Hash,
+    nonce_ix: Instruction,
+    alt: Arc<[AddressLookupTableAccount]>,
+    retry_count: u32,
+    options: UltraSubmitOptions,
+) -> UltraSubmitOutcome {
+    let (services, left_out) = match &options.routing {
+        Some(routing) => {
+            let candidates = services.clone();
+            let selected = routing.select(services, options.breakers.as_deref());
+            let left_out = candidates
+                .into_iter()
+                .filter(|candidate| {
+                    !selected.iter().any(|service| {
+                        service.name == candidate.name
+                            && service.client.region() == candidate.client.region()
+                    })
+                })
+                .collect();
+            (selected, left_out)
+        }
+        None => (services, Vec::new()),
+    };
+    // Half-open relayers get a single probe: one attempt, sent once
+    let mut probing = HashSet::new();
+    let services: Vec<ServiceConfig> = match &options.breakers {
+        Some(breakers) => services
+            .into_iter()
+            .filter(|service| {
+                let allowed = breakers.allow(service);
+                if !allowed {
+                    println!(
+                        "⛔ Skipping {} ({}), circuit open",
+                        service.name,
+                        service.client.region()
+                    );
+                } else if breakers.state(service.name, service.client.region())
+                    == BreakerState::HalfOpen
+                {
+                    probing.insert((service.name, service.client.region()));
+                }
+                allowed
+            })
+            .collect(),
+        None => services,
+    };
+    let services = match &options.rate_limits {
+        Some(rate_limits) => {
+            let left_out = match &options.breakers {
+                Some(breakers) => left_out
+                    .into_iter()
+                    .filter(|service| breakers.is_available(service))
+                    .collect(),
+                None => left_out,
+            };
+            rate_limits.spill(services, left_out)
+        }
+        None => services,
+    };
     let total_services = services.len();
-    let total_submissions = total_services * retry_count as usize;
+    let attempts_for = |service: &ServiceConfig| {
+        let retry_count = if probing.contains(&(service.name, service.client.region())) {
+            retry_count.min(1)
+        } else {
+            retry_count
+        };
+        // Attempts repeating an earlier tip and fee would resend the same signature
+        match &options.escalation {
+            Some(escalation) => {
+                escalation.distinct_attempts(&tx_info, retry_count, service.client.min_tip())
+            }
+            None => (0..retry_count).collect::<Vec<u32>>(),
+        }
+    };
+    let total_submissions: usize = services
+        .iter()
+        .map(|service| attempts_for(service).len())
+        .sum();
     
     println!("📊 Configuration: {} services × {} retries = {} total submissions", 
              total_services, retry_count, total_submissions);
@@ -306,152 +609,193 @@ pub async fn ultra_submit(
     let preparation_start = Instant::now();
     let mut handles = Vec::new();
 
-    // Create all submission tasks using the generic function
-    // Use Arc to share data efficiently across tasks
-    let tx_info_arc = Arc::new(tx_info);
-    let nonce_ix_arc = Arc::new(nonce_ix);
-    let alt_arc = Arc::new(alt);
-
-    for service in services {
-        for attempt in 0..retry_count {
-            let service_config = service.clone();
-            let tx_info_shared = Arc::clone(&tx_info_arc);
-            let nonce_ix_shared = Arc::clone(&nonce_ix_arc);
-            let alt_shared = Arc::clone(&alt_arc);
-
-            let handle = tokio::spawn(async move {
-                submit_to_service(
-                    service_config,
-                    (*tx_info_shared).clone(),
-                    signers,
-                    recent_blockhash,
-                    (*nonce_ix_shared).clone(),
-                    (*alt_shared).clone(),
-                    attempt,
-                ).await
-            });
-            handles.push(handle);
-        }
-    }
-
-    let preparation_elapsed = preparation_start.elapsed();
-    println!(
-        "⚡ Preparation phase completed in {:.2}ms",
-        preparation_elapsed.as_secs_f64() * 1000.0
-    );
-
-    // Execute all submissions simultaneously with no delays
-    let execution_start = Instant::now();
-    println!(
-        "\n🚀 Launching {} simultaneous submissions across {} services",
-        total_submissions, total_services
-    );
+    // Plan every (service, attempt) up front so the budget guard sees all of them
+    let mut planned = Vec::new();
+    let mut rejected = Vec::new();
 
-    // Execute all tasks truly simultaneously - maximum parallelism
-    let results = join_all(handles).await;
+    // Every attempt shares the blockhash, so one close to expiry rejects them all
+    let (recent_blockhash, blockhash_error) = match &options.blockhash {
+        Some(policy) => match policy.resolve(recent_blockhash).await {
+            Ok(blockhash) => (blockhash, None),
+            Err(err) => (recent_blockhash, Some(err)),
+        },
+        None => (recent_blockhash, None),
+    };
 
-    let execution_elapsed = execution_start.elapsed();
-    let total_elapsed = global_start.elapsed();
+    // Nothing would stop later tiers and resends once a signature lands
+    let schedule_error = (options.schedule.is_timed()
+        && !matches!(options.completion, CompletionPolicy::FirstConfirmed(_)))
+    .then_some("A tiered or resending SubmissionSchedule needs CompletionPolicy::FirstConfirmed");
 
-    // Collect and analyze results
-    let mut success_count = 0;
-    let mut total_latency = 0.0;
-    let mut service_stats: HashMap<String, (u32, u32, f64)> = HashMap::new(); // (success, total, total_latency)
+    for service in &services {
+        let min_tip = service.client.min_tip();
 
-    for result in results {
-        if let Ok(submission_result) = result {
-            if submission_result.success {
-                success_count += 1;
+        for attempt in attempts_for(service) {
+            if let Some(err) = &blockhash_error {
+                rejected.push(rejection(service, attempt, "blockhash policy", err));
+                continue;
             }
-            total_latency += submission_result.latency_ms;
-            
-            let stats = service_stats.entry(submission_result.service_name.clone()).or_insert((0, 0, 0.0));
-            stats.1 += 1; // total attempts
-            stats.2 += submission_result.latency_ms; // total latency
-            if submission_result.success {
-                stats.0 += 1; // successful attempts
+            if let Some(err) = &schedule_error {
+                rejected.push(rejection(service, attempt, "submission schedule", err));
+                continue;
             }
+            // Relayers would get the transaction without its tip while the budget pays for it
+            if tx_info.placement == TipPlacement::Separate {
+                let err = "TipPlacement::Separate is not supported by ultra_submit, \
+                           use Last or First";
+                rejected.push(rejection(service, attempt, "tip placement", &err));
+                continue;
+            }
+
+            // Each attempt gets its own tip so retries are distinct transactions
+            let attempt_tx_info = match &options.escalation {
+                Some(escalation) => escalation.tips_for_attempt(&tx_info, attempt),
+                None => tx_info.clone(),
+            };
+
+            let fee_lamports = match &options.budget {
+                Some(guard) => match guard.check_tx(&attempt_tx_info, min_tip) {
+                    Ok(fee_lamports) => fee_lamports,
+                    Err(err) => {
+                        rejected.push(rejection(service, attempt, "budget guard", &err));
+                        continue;
+                    }
+                },
+                None => attempt_tx_info.total_fee_lamports(min_tip),
+            };
+
+            planned.push((service.clone(), attempt, attempt_tx_info, fee_lamports));
         }
     }
 
-    // Print detailed results
-    println!(
-        "\n✅ All submissions completed in {:.2}ms (wall time)",
-        total_elapsed.as_secs_f64() * 1000.0
-    );
+    // All attempts share the durable nonce, so at most the most expensive one lands.
+    // The worst case is reserved now and settled once the outcome is known.
+    let mut reservation = None;
+    if let Some(guard) = &options.budget {
+        let worst_case_lamports = planned.iter().map(|(_, _, _, fee)| *fee).max().unwrap_or(0);
 
-    println!("\n📊 Performance Summary:");
-    println!("   • Preparation: {:.2}ms", preparation_elapsed.as_secs_f64() * 1000.0);
-    println!("   • Execution: {:.2}ms", execution_elapsed.as_secs_f64() * 1000.0);
-    println!("   • Total: {:.2}ms", total_elapsed.as_secs_f64() * 1000.0);
-    println!("   • Success Rate: {}/{} ({:.1}%)", 
-             success_count, total_submissions, 
-             (success_count as f64 / total_submissions as f64) * 100.0);
-    
-    if success_count > 0 {
-        println!("   • Average Latency: {:.2}ms", total_latency / success_count as f64);
+        match guard.try_spend(worst_case_lamports) {
+            Ok(reserved) => reservation = Some(reserved),
+            Err(err) => {
+                for (service, attempt, _, _) in planned.drain(..) {
+                    rejected.push(rejection(&service, attempt, "budget guard", &err));
+                }
+            }
+        }
     }
 
-    // Service-specific statistics
-    println!("\n📈 Service Performance:");
-    for (service_name, (success, total, latency)) in service_stats {
-        let success_rate = (success as f64 / total as f64) * 100.0;
-        let avg_latency = if success > 0 { latency / success as f64 } else { 0.0 };
-        println!("   • {}: {}/{} ({:.1}%) | Avg: {:.2}ms", 
-                 service_name, success, total, success_rate, avg_latency);
+    // Build and sign every distinct transaction before launch. Signers may block on a
+    // remote signing service, so this runs on the blocking pool.
+    let build_start = Instant::now();
+    let ledger = Arc::new(SubmissionLedger::new());
+    let (built_txs, ready, build_rejected) = {
+        let ledger = Arc::clone(&ledger);
+        let escalates = options.escalation.is_some();
+        tokio::task::spawn_blocking(move || {
+            build_planned(planned, escalates, &signers, recent_blockhash, &nonce_ix, &alt, &ledger)
+        })
+        .await
+        .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+    };
+    rejected.extend(build_rejected);
+    // Nothing to send, nothing can be paid
+    if let (Some(guard), Some(reserved)) = (&options.budget, reservation)
+        && built_txs.is_empty()
+    {
+        guard.settle(reserved, 0);
+        reservation = None;
     }
-}
 
-/// Convenience function for ultra_submit with simplified parameters
-/// This function provides a cleaner interface for common use cases
-pub async fn ultra_submit_simple(
-    tx_info: Tips,
-    signers: &'static Vec<&'static Keypair>,
-    recent_blockhash: Hash,
-    nonce_ix: Instruction,
-    alt: Vec<AddressLookupTableAccount>,
-    retry_count: u32,
-    services: Vec<ServiceConfig>,
-) {
-    let global_start = Instant::now();
-    println!("🚀 Starting ultra_submit_simple with {} services", services.len());
+    let build_elapsed = build_start.elapsed();
+    println!(
+        "🔏 Built and signed {} transactions in {:.2}ms",
+        built_txs.len(),
+        build_elapsed.as_secs_f64() * 1000.0
+    );
 
-    let total_services = services.len();
-    let total_submissions = total_services * retry_count as usize;
-    
-    println!("📊 Configuration: {} services × {} retries = {} total submissions", 
-             total_services, retry_count, total_submissions);
+    // Tasks report through a channel so resends and early returns see results as they arrive
+    let (results_tx, mut results_rx) = unbounded_channel::<SubmissionResult>();
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let escalation_arc = Arc::new(options.escalation.clone());
+    let schedule_arc = Arc::new(options.schedule.clone());
+    // Resends stop at blockhash expiry when the provider issued the blockhash
+    let expiry = options.blockhash.as_ref().and_then(|policy| {
+        policy
+            .provider
+            .lookup(&recent_blockhash)
+            .map(|info| (Arc::clone(&policy.provider), info))
+    });
 
-    // Prepare all submission tasks
-    let preparation_start = Instant::now();
-    let mut handles = Vec::new();
+    for (service_config, attempt, built, build_ms) in ready {
+        let probe = probing.contains(&(service_config.name, service_config.client.region()));
+        let escalation_shared = Arc::clone(&escalation_arc);
+        let schedule_shared = Arc::clone(&schedule_arc);
+        let expiry_shared = expiry.clone();
+        let ledger_shared = Arc::clone(&ledger);
+        let rate_limits_shared = options.rate_limits.clone();
+        let results_tx = results_tx.clone();
+        let mut stop_rx = stop_rx.clone();
 
-    // Use Arc to share data efficiently across tasks
-    let tx_info_arc = Arc::new(tx_info);
-    let nonce_ix_arc = Arc::new(nonce_ix);
-    let alt_arc = Arc::new(alt);
-
-    for service in services {
-        for attempt in 0..retry_count {
-            let service_config = service.clone();
-            let tx_info_shared = Arc::clone(&tx_info_arc);
-            let nonce_ix_shared = Arc::clone(&nonce_ix_arc);
-            let alt_shared = Arc::clone(&alt_arc);
-
-            let handle = tokio::spawn(async move {
-                submit_to_service(
-                    service_config,
-                    (*tx_info_shared).clone(),
-                    signers,
-                    recent_blockhash,
-                    (*nonce_ix_shared).clone(),
-                    (*alt_shared).clone(),
-                    attempt,
-                ).await
-            });
-            handles.push(handle);
-        }
+        let handle = tokio::spawn(async move {
+            let mut delay = schedule_shared.delay_for(service_config.name);
+            if let Some(escalation) = escalation_shared.as_ref() {
+                delay += escalation.delay_for_attempt(attempt);
+            }
+            if !delay.is_zero() && wait_or_stop(delay, &mut stop_rx).await {
+                return;
+            }
+
+            let started = Instant::now();
+            let mut marked = false;
+            loop {
+                let permit = match &rate_limits_shared {
+                    Some(rate_limits) => rate_limits.acquire(&service_config).await,
+                    None => Ok(()),
+                };
+                let result = match permit {
+                    Ok(()) => {
+                        if !marked {
+                            ledger_shared.mark_sent(
+                                &built.signature,
+                                service_config.name,
+                                service_config.client.region(),
+                                if escalation_shared.is_some() { attempt } else { 0 },
+                            );
+                            marked = true;
+                        }
+                        submit_to_service(
+                            service_config.clone(),
+                            Arc::clone(&built),
+                            build_ms,
+                            attempt,
+                        )
+                        .await
+                    }
+                    Err(err) => rejection(&service_config, attempt, "rate limiter", &err),
+                };
+                if let Some(rate_limits) = &rate_limits_shared {
+                    rate_limits.record_result(&service_config, &result);
+                }
+                let _ = results_tx.send(result);
+
+                let Some(resend) = schedule_shared.resend.as_ref().filter(|_| !probe) else {
+                    break;
+                };
+                // Whichever comes first, `max_duration_ms` or the blockhash expiry
+                let expired = started.elapsed() >= Duration::from_millis(resend.max_duration_ms)
+                    || expiry_shared
+                        .as_ref()
+                        .is_some_and(|(provider, info)| provider.blocks_remaining(info) == 0);
+                if expired
+                    || wait_or_stop(Duration::from_millis(resend.interval_ms), &mut stop_rx).await
+                {
+                    break;
+                }
+            }
+        });
+        handles.push(handle);
     }
+    drop(results_tx);
 
     let preparation_elapsed = preparation_start.elapsed();
     println!(
@@ -467,7 +811,136 @@ pub async fn ultra_submit_simple(
     );
 
     // Execute all tasks truly simultaneously - maximum parallelism
-    let results = join_all(handles).await;
+    for result in &rejected {
+        publish(&options.results, result);
+    }
+    let mut results = rejected;
+
+    let signatures: Vec<Signature> = built_txs.values().map(|(built, _)| built.signature).collect();
+    let confirmation = async {
+        match &options.completion {
+            CompletionPolicy::FirstConfirmed(wait) if !signatures.is_empty() => {
+                wait.wait_any(&signatures).await
+            }
+            _ => std::future::pending::<Option<(Signature, ConfirmationStatus)>>().await,
+        }
+    };
+    tokio::pin!(confirmation);
+
+    let mut first_accepted: Option<SubmissionResult> = None;
+    let mut confirmation_status = None;
+    let mut completed_early = false;
+
+    loop {
+        tokio::select! {
+            next = results_rx.recv() => match next {
+                Some(result) => {
+                    publish(&options.results, &result);
+                    if let Some(breakers) = &options.breakers {
+                        breakers.record_result(&result);
+                    }
+                    if result.success && first_accepted.is_none() {
+                        first_accepted = Some(result.clone());
+                    }
+                    let accepted = result.success;
+                    results.push(result);
+
+                    if accepted && matches!(options.completion, CompletionPolicy::FirstAccepted) {
+                        completed_early = handles.iter().any(|handle| !handle.is_finished());
+                        break;
+                    }
+                }
+                None => {
+                    // Everything was sent; keep waiting for the confirmation if asked to
+                    if matches!(options.completion, CompletionPolicy::FirstConfirmed(_))
+                        && first_accepted.is_some()
+                    {
+                        confirmation_status = confirmation.as_mut().await;
+                    }
+                    break;
+                }
+            },
+            confirmed = confirmation.as_mut() => {
+                confirmation_status = confirmed;
+                completed_early = handles.iter().any(|handle| !handle.is_finished());
+                break;
+            }
+        }
+    }
+
+    // Later tiers and resends only run while nothing is confirmed
+    let confirmed_signature = confirmation_status.as_ref().map(|(signature, _)| *signature);
+    let mut landing = None;
+    if let Some((signature, status)) = &confirmation_status {
+        println!("🎯 Signature {} landed: {:?}", signature, status);
+        let _ = stop_tx.send(true);
+
+        if let CompletionPolicy::FirstConfirmed(wait) = &options.completion {
+            landing = ledger
+                .attribute(signature, status, &wait.tracker.rpc_client)
+                .await;
+        }
+        if let Some(landing) = &landing {
+            println!(
+                "🏁 Landed by {} ({}) attempt #{} in slot {} (leader {}) after {:.2}ms",
+                landing.service_name,
+                landing.region,
+                landing.attempt + 1,
+                landing.slot,
+                landing
+                    .slot_leader
+                    .map(|leader| leader.to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                landing
+                    .time_to_land
+                    .map(|elapsed| elapsed.as_secs_f64() * 1000.0)
+                    .unwrap_or_default()
+            );
+        }
+    }
+
+    // Only the landed transaction's fee is paid. Without a landing the reservation stays
+    // and goes back to the caller, a transaction may still land after ultra_submit returns.
+    if let (Some(guard), Some(reserved), Some(landing)) = (&options.budget, reservation, &landing) {
+        guard.settle(reserved, landing.fee_lamports);
+        reservation = None;
+    }
+
+    let in_flight = handles.iter().filter(|handle| !handle.is_finished()).count();
+    if completed_early {
+        match options.remainder {
+            RemainderPolicy::Cancel => {
+                for handle in &handles {
+                    handle.abort();
+                }
+                println!("🛑 Cancelled {} in-flight submissions", in_flight);
+            }
+            RemainderPolicy::Keep => {
+                let results_tx = options.results.clone();
+                let stats = options.stats.clone();
+                let breakers = options.breakers.clone();
+                let ledger = Arc::clone(&ledger);
+                let landing = landing.clone();
+                tokio::spawn(async move {
+                    // Holding the stop sender keeps scheduled tasks listening for it
+                    let _stop_tx = stop_tx;
+                    while let Some(result) = results_rx.recv().await {
+                        publish(&results_tx, &result);
+                        if let Some(stats) = &stats {
+                            stats.record_submission(&result);
+                        }
+                        if let Some(breakers) = &breakers {
+                            breakers.record_result(&result);
+                        }
+                    }
+                    // Sends are counted once the schedule has run its course
+                    if let Some(stats) = &stats {
+                        stats.record_sends(&ledger, landing.as_ref());
+                    }
+                });
+            }
+        }
+    }
 
     let execution_elapsed = execution_start.elapsed();
     let total_elapsed = global_start.elapsed();
@@ -477,30 +950,37 @@ pub async fn ultra_submit_simple(
     let mut total_latency = 0.0;
     let mut service_stats: HashMap<String, (u32, u32, f64)> = HashMap::new(); // (success, total, total_latency)
 
-    for result in results {
-        if let Ok(submission_result) = result {
-            if submission_result.success {
-                success_count += 1;
-            }
-            total_latency += submission_result.latency_ms;
-            
-            let stats = service_stats.entry(submission_result.service_name.clone()).or_insert((0, 0, 0.0));
-            stats.1 += 1; // total attempts
-            stats.2 += submission_result.latency_ms; // total latency
-            if submission_result.success {
-                stats.0 += 1; // successful attempts
-            }
+    for submission_result in &results {
+        if submission_result.success {
+            success_count += 1;
+        }
+        total_latency += submission_result.latency_ms;
+
+        let stats = service_stats.entry(submission_result.service_name.clone()).or_insert((0, 0, 0.0));
+        stats.1 += 1; // total attempts
+        stats.2 += submission_result.latency_ms; // total latency
+        if submission_result.success {
+            stats.0 += 1; // successful attempts
         }
     }
 
     // Print detailed results
-    println!(
-        "\n✅ All submissions completed in {:.2}ms (wall time)",
-        total_elapsed.as_secs_f64() * 1000.0
-    );
+    if completed_early {
+        println!(
+            "\n✅ Completed early after {:.2}ms (wall time), {} submissions still in flight",
+            total_elapsed.as_secs_f64() * 1000.0,
+            in_flight
+        );
+    } else {
+        println!(
+            "\n✅ All submissions completed in {:.2}ms (wall time)",
+            total_elapsed.as_secs_f64() * 1000.0
+        );
+    }
 
     println!("\n📊 Performance Summary:");
     println!("   • Preparation: {:.2}ms", preparation_elapsed.as_secs_f64() * 1000.0);
+    println!("   • Build & sign: {:.2}ms", build_elapsed.as_secs_f64() * 1000.0);
     println!("   • Execution: {:.2}ms", execution_elapsed.as_secs_f64() * 1000.0);
     println!("   • Total: {:.2}ms", total_elapsed.as_secs_f64() * 1000.0);
     println!("   • Success Rate: {}/{} ({:.1}%)", 
@@ -519,4 +999,77 @@ pub async fn ultra_submit_simple(
         println!("   • {}: {}/{} ({:.1}%) | Avg: {:.2}ms", 
                  service_name, success, total, success_rate, avg_latency);
     }
+
+    let outcome = UltraSubmitOutcome {
+        results,
+        first_accepted,
+        confirmed_signature,
+        confirmation: confirmation_status.map(|(_, status)| status),
+        landing,
+        ledger,
+        reservation,
+        completed_early,
+    };
+
+    if let Some(stats) = &options.stats {
+        for result in &outcome.results {
+            stats.record_submission(result);
+        }
+        // With kept in-flight submissions the drain task counts the sends
+        if !(completed_early && options.remainder == RemainderPolicy::Keep) {
+            stats.record_sends(&outcome.ledger, outcome.landing.as_ref());
+        }
+    }
+
+    outcome
+}
+
+/// Sleeps for `delay`, returning early with `true` once the stop signal is raised.
+/// A dropped stop sender means ultra_submit is gone, which stops as well.
+async fn wait_or_stop(delay: Duration, stop_rx: &mut watch::Receiver<bool>) -> bool {
+    let stopped = tokio::select! {
+        _ = sleep(delay) => false,
+        _ = stop_rx.wait_for(|stopped| *stopped) => true,
+    };
+    stopped || *stop_rx.borrow()
+}
+
+fn publish(results_tx: &Option<UnboundedSender<SubmissionResult>>, result: &SubmissionResult) {
+    if let Some(results_tx) = results_tx {
+        let _ = results_tx.send(result.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn wait_or_stop_returns_on_stop_signal() {
+        let (stop_tx, mut stop_rx) = watch::channel(false);
+        let start = Instant::now();
+        let waiting = tokio::spawn(async move {
+            wait_or_stop(Duration::from_secs(10), &mut stop_rx).await
+        });
+
+        stop_tx.send(true).unwrap();
+        assert!(waiting.await.unwrap());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn dropped_stop_sender_stops() {
+        let (stop_tx, mut stop_rx) = watch::channel(false);
+        drop(stop_tx);
+
+        let start = Instant::now();
+        assert!(wait_or_stop(Duration::from_secs(10), &mut stop_rx).await);
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn wait_or_stop_sleeps_without_a_signal() {
+        let (_stop_tx, mut stop_rx) = watch::channel(false);
+        assert!(!wait_or_stop(Duration::from_millis(10), &mut stop_rx).await);
+    }
 }
@@ -0,0 +1,203 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+/// One relayer a signed transaction was handed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmissionRecord {
+    pub service_name: &'static str,
+    /// Endpoint the relayer was reached at, e.g. `Jito-Amsterdam`
+    pub region: &'static str,
+    pub attempt: u32,
+    /// Priority fee and tip the transaction pays if it lands
+    pub fee_lamports: u64,
+    /// First send to this relayer, `None` while it waits on its schedule
+    pub first_sent_at: Option<Instant>,
+}
+
+/// Which relayer landed the transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LandingAttribution {
+    pub signature: Signature,
+    pub service_name: &'static str,
+    pub region: &'static str,
+    pub attempt: u32,
+    pub fee_lamports: u64,
+    pub slot: Slot,
+    pub slot_leader: Option<Pubkey>,
+    /// First send of the landed transaction until its confirmation was observed
+    pub time_to_land: Option<Duration>,
+    /// Relayers sent the identical transaction, the earliest sender is credited
+    pub shared_with: Vec<&'static str>,
+}
+
+/// Signature → relayer records of one `ultra_submit` call
+#[derive(Debug, Default)]
+pub struct SubmissionLedger {
+    records: Mutex<HashMap<Signature, Vec<SubmissionRecord>>>,
+}
+
+impl SubmissionLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, signature: Signature, record: SubmissionRecord) {
+        self.records
+            .lock()
+            .unwrap()
+            .entry(signature)
+            .or_default()
+            .push(record);
+    }
+
+    /// Stamps the first send of `signature` to `service_name` in `region`
+    pub fn mark_sent(&self, signature: &Signature, service_name: &str, region: &str, attempt: u32) {
+        let mut records = self.records.lock().unwrap();
+        if let Some(record) = records.get_mut(signature).and_then(|records| {
+            records.iter_mut().find(|record| {
+                record.service_name == service_name
+                    && record.region == region
+                    && record.attempt == attempt
+            })
+        }) {
+            record.first_sent_at.get_or_insert_with(Instant::now);
+        }
+    }
+
+    pub fn get(&self, signature: &Signature) -> Vec<SubmissionRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .get(signature)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn records(&self) -> Vec<(Signature, SubmissionRecord)> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|(signature, records)| {
+                records.iter().map(|record| (*signature, record.clone()))
+            })
+            .collect()
+    }
+
+    pub fn signatures(&self) -> Vec<Signature> {
+        self.records.lock().unwrap().keys().copied().collect()
+    }
+
+    /// Credits `signature`, landed with `status`, to the relayer that sent it first.
+    /// `rpc_client` looks up the slot leader.
+    pub async fn attribute(
+        &self,
+        signature: &Signature,
+        status: &ConfirmationStatus,
+        rpc_client: &RpcClient,
+    ) -> Option<LandingAttribution> {
+        let observed_at = Instant::now();
+        let slot = status.slot()?;
+        let mut records = self.get(signature);
+        // Sent records first, earliest first
+        records.sort_by_key(|record| (record.first_sent_at.is_none(), record.first_sent_at));
+        let mut records = records.into_iter();
+        let winner = records.next()?;
+
+        let slot_leader = match rpc_client.get_slot_leaders(slot, 1).await {
+            Ok(leaders) => leaders.first().copied(),
+            Err(err) => {
+                eprintln!("Slot leader lookup for slot {} failed: {}", slot, err);
+                None
+            }
+        };
+
+        Some(LandingAttribution {
+            signature: *signature,
+            service_name: winner.service_name,
+            region: winner.region,
+            attempt: winner.attempt,
+            fee_lamports: winner.fee_lamports,
+            slot,
+            slot_leader,
+            time_to_land: winner
+                .first_sent_at
+                .map(|sent_at| observed_at.saturating_duration_since(sent_at)),
+            shared_with: records.map(|record| record.service_name).collect(),
+        })
+    }
+
+    /// Waits for any recorded signature to confirm and attributes it
+    pub async fn wait_for_landing(&self, wait: &ConfirmationWait) -> Option<LandingAttribution> {
+        let (signature, status) = wait.wait_any(&self.signatures()).await?;
+        self.attribute(&signature, &status, &wait.tracker.rpc_client)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(region: &'static str) -> SubmissionRecord {
+        SubmissionRecord {
+            service_name: "Jito",
+            region,
+            attempt: 0,
+            fee_lamports: 1_000,
+            first_sent_at: None,
+        }
+    }
+
+    #[test]
+    fn regions_of_one_service_are_tracked_separately() {
+        let ledger = SubmissionLedger::new();
+        let amsterdam = Signature::from([1; 64]);
+        let tokyo = Signature::from([2; 64]);
+        ledger.record(amsterdam, record("Jito-Amsterdam"));
+        ledger.record(tokyo, record("Jito-Tokyo"));
+
+        ledger.mark_sent(&tokyo, "Jito", "Jito-Tokyo", 0);
+        // Another region's name never stamps this signature
+        ledger.mark_sent(&amsterdam, "Jito", "Jito-Tokyo", 0);
+
+        assert!(ledger.get(&tokyo)[0].first_sent_at.is_some());
+        assert!(ledger.get(&amsterdam)[0].first_sent_at.is_none());
+        assert_eq!(ledger.records().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn landing_is_credited_to_the_earliest_sender() {
+        let ledger = SubmissionLedger::new();
+        let signature = Signature::from([3; 64]);
+        ledger.record(signature, record("Jito-Amsterdam"));
+        ledger.record(signature, record("Jito-Tokyo"));
+        ledger.mark_sent(&signature, "Jito", "Jito-Tokyo", 0);
+        std::thread::sleep(Duration::from_millis(2));
+        ledger.mark_sent(&signature, "Jito", "Jito-Amsterdam", 0);
+
+        // Unreachable RPC, the slot leader is simply unknown
+        let rpc_client = RpcClient::new("http://127.0.0.1:1".to_string());
+        let landing = ledger
+            .attribute(
+                &signature,
+                &ConfirmationStatus::Confirmed { slot: 42 },
+                &rpc_client,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(landing.region, "Jito-Tokyo");
+        assert_eq!(landing.slot, 42);
+        assert_eq!(landing.slot_leader, None);
+        assert_eq!(landing.shared_with, vec!["Jito"]);
+        assert!(landing.time_to_land.is_some());
+    }
+}
@@ -0,0 +1,421 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::{task::JoinHandle, time::sleep};
+
+use crate::*;
+
+pub const BREAKER_FAILURE_THRESHOLD: u32 = 5;
+pub const BREAKER_COOLDOWN_MS: u64 = 30_000;
+pub const HEALTH_PROBE_TIMEOUT_MS: u64 = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Sending normally
+    Closed,
+    /// Skipped until the cooldown ends
+    Open { until: Instant },
+    /// Cooldown over, the next submission probes the relayer
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerConfig {
+    /// Consecutive failures that open the breaker
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: BREAKER_FAILURE_THRESHOLD,
+            cooldown: Duration::from_millis(BREAKER_COOLDOWN_MS),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    /// A half-open probe was let through and has not reported back. A probe
+    /// silent for a whole cooldown (e.g. cancelled) lets the next one through.
+    probe_started_at: Option<Instant>,
+}
+
+impl Breaker {
+    fn new() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            consecutive_failures: 0,
+            probe_started_at: None,
+        }
+    }
+
+    /// Moves an open breaker whose cooldown ended to half-open
+    fn refresh(&mut self) -> BreakerState {
+        if let BreakerState::Open { until } = self.state
+            && Instant::now() >= until
+        {
+            self.state = BreakerState::HalfOpen;
+            self.probe_started_at = None;
+        }
+        self.state
+    }
+}
+
+/// Per relayer and region circuit breakers fed by submission errors and health checks
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    pub config: BreakerConfig,
+    breakers: Mutex<HashMap<RelayerKey, Breaker>>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: BreakerConfig) -> Self {
+        Self {
+            config,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn key(service_name: &str, region: &str) -> RelayerKey {
+        RelayerKey {
+            service_name: service_name.to_string(),
+            region: region.to_string(),
+        }
+    }
+
+    pub fn state(&self, service_name: &str, region: &str) -> BreakerState {
+        self.breakers
+            .lock()
+            .unwrap()
+            .get_mut(&Self::key(service_name, region))
+            .map(|breaker| breaker.refresh())
+            .unwrap_or(BreakerState::Closed)
+    }
+
+    /// Whether `service` may be sent to now, without taking the half-open probe
+    pub fn is_available(&self, service: &ServiceConfig) -> bool {
+        match self.state(service.name, service.client.region()) {
+            BreakerState::Closed | BreakerState::HalfOpen => true,
+            BreakerState::Open { .. } => false,
+        }
+    }
+
+    /// Whether to send to `service`. A half-open breaker lets a single probe through.
+    pub fn allow(&self, service: &ServiceConfig) -> bool {
+        let mut breakers = self.breakers.lock().unwrap();
+        let Some(breaker) = breakers.get_mut(&Self::key(service.name, service.client.region()))
+        else {
+            return true;
+        };
+
+        match breaker.refresh() {
+            BreakerState::Closed => true,
+            BreakerState::Open { .. } => false,
+            BreakerState::HalfOpen
+                if breaker
+                    .probe_started_at
+                    .is_some_and(|started| started.elapsed() < self.config.cooldown) =>
+            {
+                false
+            }
+            BreakerState::HalfOpen => {
+                breaker.probe_started_at = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self, service_name: &str, region: &str) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let breaker = breakers
+            .entry(Self::key(service_name, region))
+            .or_insert_with(Breaker::new);
+
+        match breaker.refresh() {
+            BreakerState::Closed => breaker.consecutive_failures = 0,
+            BreakerState::HalfOpen => {
+                println!("🟢 Circuit closed for {} ({})", service_name, region);
+                *breaker = Breaker::new();
+            }
+            // Only a probe after the cooldown closes an open breaker
+            BreakerState::Open { .. } => {}
+        }
+    }
+
+    pub fn record_failure(&self, service_name: &str, region: &str, reason: &str) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let breaker = breakers
+            .entry(Self::key(service_name, region))
+            .or_insert_with(Breaker::new);
+
+        breaker.consecutive_failures += 1;
+        let trips = match breaker.refresh() {
+            BreakerState::Closed => breaker.consecutive_failures >= self.config.failure_threshold,
+            BreakerState::HalfOpen => true,
+            BreakerState::Open { .. } => false,
+        };
+
+        if trips {
+            breaker.state = BreakerState::Open {
+                until: Instant::now() + self.config.cooldown,
+            };
+            breaker.probe_started_at = None;
+            eprintln!(
+                "🔴 Circuit opened for {} ({}) for {:?} after {} failures: {}",
+                service_name, region, self.config.cooldown, breaker.consecutive_failures, reason
+            );
+        }
+    }
+
+    /// Feeds a submission result. Rejections before sending and 429s, which the
+    /// rate limiter handles, say nothing about the relayer's health.
+    pub fn record_result(&self, result: &SubmissionResult) {
+        if result.signature.is_none() || result.rate_limited.is_some() {
+            return;
+        }
+        match &result.error {
+            None => self.record_success(&result.service_name, &result.region),
+            Some(error) => self.record_failure(&result.service_name, &result.region, error),
+        }
+    }
+
+    /// Outcome of a health probe. Only a half-open breaker reacts: a reachable relayer
+    /// closes it, an unreachable one reopens it. Submission failure counts are never reset.
+    pub fn record_probe(&self, service_name: &str, region: &str, result: Result<(), String>) {
+        let mut breakers = self.breakers.lock().unwrap();
+        let Some(breaker) = breakers.get_mut(&Self::key(service_name, region)) else {
+            return;
+        };
+        if breaker.refresh() != BreakerState::HalfOpen {
+            return;
+        }
+
+        match result {
+            Ok(()) => {
+                println!(
+                    "🟢 Circuit closed for {} ({}) by health probe",
+                    service_name, region
+                );
+                *breaker = Breaker::new();
+            }
+            Err(reason) => {
+                breaker.state = BreakerState::Open {
+                    until: Instant::now() + self.config.cooldown,
+                };
+                breaker.probe_started_at = None;
+                eprintln!(
+                    "🔴 Circuit reopened for {} ({}) for {:?}: {}",
+                    service_name, region, self.config.cooldown, reason
+                );
+            }
+        }
+    }
+
+    /// Every `interval`, probes relayers whose breaker finished its cooldown with an HTTP
+    /// request through the relayer's own client. Closed breakers are left to submissions.
+    pub fn spawn_health_checks(
+        self: Arc<Self>,
+        services: Vec<ServiceConfig>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                for service in &services {
+                    let region = service.client.region();
+                    if self.state(service.name, region) != BreakerState::HalfOpen {
+                        continue;
+                    }
+                    let result = probe(&service.client).await;
+                    self.record_probe(service.name, region, result);
+                }
+                sleep(interval).await;
+            }
+        })
+    }
+}
+
+/// Only a 2xx answer means the relayer is healthy. Auth and other client errors
+/// keep the breaker open, the relayer would reject submissions the same way.
+async fn probe(client: &ServiceClient) -> Result<(), String> {
+    let (http_client, url) = client.http_endpoint();
+    let response = http_client
+        .get(url)
+        .timeout(Duration::from_millis(HEALTH_PROBE_TIMEOUT_MS))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    probe_status(response.status())
+}
+
+fn probe_status(status: reqwest::StatusCode) -> Result<(), String> {
+    if !status.is_success() {
+        return Err(format!("health probe returned {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ultra_submit::ultra_submit_test_support::jito_service;
+    use solana_sdk::signature::Signature;
+
+    fn config(cooldown_ms: u64) -> BreakerConfig {
+        BreakerConfig {
+            failure_threshold: 3,
+            cooldown: Duration::from_millis(cooldown_ms),
+        }
+    }
+
+    fn fail(breakers: &CircuitBreakers, service: &ServiceConfig) {
+        breakers.record_failure(service.name, service.client.region(), "timeout");
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breakers = CircuitBreakers::new(config(60_000));
+        let service = jito_service("Jito", 1, None);
+
+        fail(&breakers, &service);
+        fail(&breakers, &service);
+        // A success in between resets the streak
+        breakers.record_success(service.name, service.client.region());
+        fail(&breakers, &service);
+        fail(&breakers, &service);
+        assert!(breakers.allow(&service));
+
+        fail(&breakers, &service);
+        assert!(matches!(
+            breakers.state(service.name, service.client.region()),
+            BreakerState::Open { .. }
+        ));
+        assert!(!breakers.allow(&service));
+        assert!(!breakers.is_available(&service));
+    }
+
+    #[test]
+    fn half_open_lets_one_probe_through() {
+        let breakers = CircuitBreakers::new(config(10));
+        let service = jito_service("Jito", 1, None);
+        for _ in 0..3 {
+            fail(&breakers, &service);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert_eq!(
+            breakers.state(service.name, service.client.region()),
+            BreakerState::HalfOpen
+        );
+        assert!(breakers.allow(&service));
+        assert!(!breakers.allow(&service));
+
+        breakers.record_success(service.name, service.client.region());
+        assert_eq!(
+            breakers.state(service.name, service.client.region()),
+            BreakerState::Closed
+        );
+    }
+
+    #[test]
+    fn only_success_statuses_pass_the_health_probe() {
+        use reqwest::StatusCode;
+
+        assert!(probe_status(StatusCode::OK).is_ok());
+        assert!(probe_status(StatusCode::NO_CONTENT).is_ok());
+        for status in [
+            StatusCode::UNAUTHORIZED,
+            StatusCode::FORBIDDEN,
+            StatusCode::NOT_FOUND,
+            StatusCode::METHOD_NOT_ALLOWED,
+            StatusCode::SERVICE_UNAVAILABLE,
+        ] {
+            assert!(probe_status(status).is_err(), "{} passed", status);
+        }
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let breakers = CircuitBreakers::new(config(10));
+        let service = jito_service("Jito", 1, None);
+        for _ in 0..3 {
+            fail(&breakers, &service);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert!(breakers.allow(&service));
+        fail(&breakers, &service);
+        assert!(!breakers.allow(&service));
+    }
+
+    #[test]
+    fn health_probes_only_affect_half_open_breakers() {
+        let breakers = CircuitBreakers::new(config(10));
+        let service = jito_service("Jito", 1, None);
+        let region = service.client.region();
+
+        // A reachable relayer does not wipe out submission failures
+        fail(&breakers, &service);
+        fail(&breakers, &service);
+        breakers.record_probe(service.name, region, Ok(()));
+        fail(&breakers, &service);
+        assert!(matches!(
+            breakers.state(service.name, region),
+            BreakerState::Open { .. }
+        ));
+
+        // Still cooling down, the probe is ignored
+        breakers.record_probe(service.name, region, Ok(()));
+        assert!(matches!(
+            breakers.state(service.name, region),
+            BreakerState::Open { .. }
+        ));
+
+        std::thread::sleep(Duration::from_millis(20));
+        breakers.record_probe(service.name, region, Err("unreachable".to_string()));
+        assert!(matches!(
+            breakers.state(service.name, region),
+            BreakerState::Open { .. }
+        ));
+
+        std::thread::sleep(Duration::from_millis(20));
+        breakers.record_probe(service.name, region, Ok(()));
+        assert_eq!(breakers.state(service.name, region), BreakerState::Closed);
+    }
+
+    #[test]
+    fn pre_send_rejections_and_rate_limits_are_ignored() {
+        let breakers = CircuitBreakers::new(config(60_000));
+        let service = jito_service("Jito", 1, None);
+        let mut result = SubmissionResult {
+            service_name: service.name.to_string(),
+            region: service.client.region().to_string(),
+            attempt: 0,
+            success: false,
+            latency_ms: 0.0,
+            build_ms: 0.0,
+            signature: None,
+            error: Some("budget".to_string()),
+            rate_limited: None,
+        };
+        for _ in 0..5 {
+            breakers.record_result(&result);
+        }
+        result.signature = Some(Signature::default());
+        result.rate_limited = Some(RateLimited { retry_after: None });
+        for _ in 0..5 {
+            breakers.record_result(&result);
+        }
+
+        assert_eq!(
+            breakers.state(service.name, service.client.region()),
+            BreakerState::Closed
+        );
+    }
+}
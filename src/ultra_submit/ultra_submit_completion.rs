@@ -0,0 +1,120 @@
+use futures::{StreamExt, stream::FuturesUnordered};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use std::{sync::Arc, time::Duration};
+use tokio::time::timeout;
+
+use crate::*;
+
+/// Tracker used to watch the pre-built signatures when completing on confirmation
+#[derive(Debug, Clone)]
+pub struct ConfirmationWait {
+    pub tracker: Arc<ConfirmationTracker>,
+    pub commitment: CommitmentConfig,
+    pub timeout: Duration,
+}
+
+impl ConfirmationWait {
+    pub fn new(tracker: Arc<ConfirmationTracker>) -> Self {
+        let timeout = tracker.timeout;
+        Self {
+            tracker,
+            commitment: CommitmentConfig::confirmed(),
+            timeout,
+        }
+    }
+
+    /// Whether `status` settles the submission. Every attempt shares the durable nonce,
+    /// so once one lands, even failed, no other attempt can.
+    pub fn resolves(&self, status: &ConfirmationStatus) -> bool {
+        status.satisfies(self.commitment) || matches!(status, ConfirmationStatus::Failed { .. })
+    }
+
+    /// First of `signatures` to reach `commitment` or land failed, `None` on timeout
+    /// or once every signature expired
+    pub async fn wait_any(
+        &self,
+        signatures: &[Signature],
+    ) -> Option<(Signature, ConfirmationStatus)> {
+        let mut waits: FuturesUnordered<_> = signatures
+            .iter()
+            .map(|signature| async move {
+                let status = self.tracker.wait(*signature, self.commitment, None).await;
+                (*signature, status)
+            })
+            .collect();
+
+        let confirmed = async {
+            while let Some((signature, status)) = waits.next().await {
+                if self.resolves(&status) {
+                    return Some((signature, status));
+                }
+            }
+            None
+        };
+
+        timeout(self.timeout, confirmed).await.ok().flatten()
+    }
+}
+
+/// When ultra_submit returns
+#[derive(Debug, Clone, Default)]
+pub enum CompletionPolicy {
+    /// Wait for every submission
+    #[default]
+    All,
+    /// Return once any relayer accepts the transaction
+    FirstAccepted,
+    /// Return once any pre-built signature is confirmed on chain
+    FirstConfirmed(ConfirmationWait),
+}
+
+/// What happens to in-flight submissions when ultra_submit returns early
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RemainderPolicy {
+    /// Let them finish in the background, their results still reach `UltraSubmitOptions::results`
+    #[default]
+    Keep,
+    /// Abort them, including attempts still waiting on their escalation delay
+    Cancel,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UltraSubmitOutcome {
+    /// Results collected before returning, rejections included
+    pub results: Vec<SubmissionResult>,
+    pub first_accepted: Option<SubmissionResult>,
+    pub confirmed_signature: Option<Signature>,
+    /// Status of `confirmed_signature`, with its slot. `Failed` when it landed with an error.
+    pub confirmation: Option<ConfirmationStatus>,
+    /// Relayer credited with `confirmed_signature`
+    pub landing: Option<LandingAttribution>,
+    /// Every signature sent and by whom, for attributing a landing after returning
+    pub ledger: Arc<SubmissionLedger>,
+    /// Worst case still held against the budget when nothing landed before returning.
+    /// `settle` it once `ledger.wait_for_landing` finds the fee paid, or with 0.
+    pub reservation: Option<BudgetReservation>,
+    /// Returned before every submission finished
+    pub completed_early: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::TransactionError;
+
+    #[test]
+    fn landed_failures_resolve_the_wait() {
+        let wait = ConfirmationWait::new(Arc::new(ConfirmationTracker::new(
+            "http://127.0.0.1:1".to_string(),
+        )));
+
+        assert!(wait.resolves(&ConfirmationStatus::Confirmed { slot: 1 }));
+        assert!(wait.resolves(&ConfirmationStatus::Failed {
+            slot: 1,
+            err: TransactionError::InsufficientFundsForFee,
+        }));
+        assert!(!wait.resolves(&ConfirmationStatus::Processed { slot: 1 }));
+        assert!(!wait.resolves(&ConfirmationStatus::Expired));
+        assert!(!wait.resolves(&ConfirmationStatus::Pending));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::*;
+
+/// Worst-case cost of one relayer's transaction across all its attempts
+#[derive(Debug, Clone)]
+pub struct RelayerCost {
+    pub service_name: String,
+    pub base_fee_lamports: u64,
+    pub priority_fee_lamports: u64,
+    pub tip_lamports: u64,
+    pub total_lamports: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CostEstimate {
+    pub per_relayer: Vec<RelayerCost>,
+    /// All submissions share the durable nonce, so only the most expensive one can land
+    pub max_total_lamports: u64,
+    /// Cost if every relayer's transaction landed (no shared nonce)
+    pub all_landed_lamports: u64,
+}
+
+/// Worst-case cost of an ultra_submit call before it is fired
+pub fn estimate_cost(
+    tx_info: &Tips,
+    services: &[ServiceConfig],
+    retry_count: u32,
+    escalation: Option<&TipEscalation>,
+    num_signatures: u64,
+) -> CostEstimate {
+    let base_fee_lamports = LAMPORTS_PER_SIGNATURE.saturating_mul(num_signatures);
+
+    let per_relayer: Vec<RelayerCost> = services
+        .iter()
+        .map(|service| {
+            let min_tip = service.client.min_tip();
+
+            (0..retry_count.max(1))
+                .map(|attempt| {
+                    let tips = match escalation {
+                        Some(escalation) => escalation.tips_for_attempt(tx_info, attempt),
+                        None => tx_info.clone(),
+                    };
+                    let priority_fee_lamports = tips.priority_fee_lamports();
+                    let tip_lamports = tips.tip_lamports(min_tip);
+
+                    RelayerCost {
+                        service_name: service.name.to_string(),
+                        base_fee_lamports,
+                        priority_fee_lamports,
+                        tip_lamports,
+                        total_lamports: base_fee_lamports
+                            .saturating_add(priority_fee_lamports)
+                            .saturating_add(tip_lamports),
+                    }
+                })
+                .max_by_key(|cost| cost.total_lamports)
+                .expect("at least one attempt")
+        })
+        .collect();
+
+    let max_total_lamports = per_relayer
+        .iter()
+        .map(|cost| cost.total_lamports)
+        .max()
+        .unwrap_or(0);
+    let all_landed_lamports = per_relayer
+        .iter()
+        .fold(0u64, |acc, cost| acc.saturating_add(cost.total_lamports));
+
+    CostEstimate {
+        per_relayer,
+        max_total_lamports,
+        all_landed_lamports,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ultra_submit::ultra_submit_test_support::jito_service;
+    use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+    fn tips() -> Tips {
+        Tips {
+            tip_sol_amount: 0.001,
+            cu: Some(200_000),
+            priority_fee_micro_lamport: Some(10_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sums_base_priority_and_tip() {
+        let estimate = estimate_cost(&tips(), &[jito_service("Jito", 0, None)], 1, None, 2);
+        let cost = &estimate.per_relayer[0];
+
+        assert_eq!(cost.base_fee_lamports, 2 * LAMPORTS_PER_SIGNATURE);
+        // 200k CU at 10k micro-lamports each
+        assert_eq!(cost.priority_fee_lamports, 2_000);
+        assert_eq!(cost.tip_lamports, LAMPORTS_PER_SOL / 1_000);
+        assert_eq!(cost.total_lamports, 10_000 + 2_000 + 1_000_000);
+        assert_eq!(estimate.max_total_lamports, cost.total_lamports);
+    }
+
+    #[test]
+    fn relayer_minimum_tip_applies() {
+        let tips = Tips {
+            tip_sol_amount: 0.0,
+            ..tips()
+        };
+        let estimate = estimate_cost(&tips, &[jito_service("Jito", 0, None)], 1, None, 1);
+
+        assert_eq!(
+            estimate.per_relayer[0].tip_lamports,
+            tips.tip_lamports(JITO_MIN_TIP)
+        );
+        assert!(estimate.per_relayer[0].tip_lamports > 0);
+    }
+
+    #[test]
+    fn takes_the_most_expensive_escalated_attempt() {
+        let escalation = TipEscalation {
+            tip_multiplier: 2.0,
+            ..Default::default()
+        };
+        let estimate = estimate_cost(
+            &tips(),
+            &[jito_service("Jito", 0, None)],
+            3,
+            Some(&escalation),
+            1,
+        );
+
+        assert_eq!(estimate.per_relayer[0].tip_lamports, 4_000_000);
+    }
+
+    #[test]
+    fn only_one_relayer_can_land() {
+        let services = [
+            jito_service("Jito", 0, None),
+            jito_service("LilJit", 0, None),
+        ];
+        let estimate = estimate_cost(&tips(), &services, 1, None, 1);
+
+        assert_eq!(estimate.per_relayer.len(), 2);
+        assert_eq!(
+            estimate.all_landed_lamports,
+            2 * estimate.max_total_lamports
+        );
+
+        let none = estimate_cost(&tips(), &[], 1, None, 1);
+        assert_eq!(none.max_total_lamports, 0);
+    }
+
+    #[test]
+    fn huge_fees_saturate_instead_of_overflowing() {
+        let tips = Tips {
+            tip_sol_amount: 1e12,
+            cu: Some(1_400_000),
+            priority_fee_micro_lamport: Some(u64::MAX),
+            ..tips()
+        };
+        let services = [
+            jito_service("Jito", 0, None),
+            jito_service("LilJit", 0, None),
+        ];
+        let estimate = estimate_cost(&tips, &services, 1, None, u64::MAX);
+
+        assert_eq!(estimate.max_total_lamports, u64::MAX);
+        assert_eq!(estimate.all_landed_lamports, u64::MAX);
+    }
+}
@@ -0,0 +1,202 @@
+use std::{collections::HashSet, time::Duration};
+
+use crate::Tips;
+
+/// Per-attempt tip and priority fee escalation for ultra_submit retries
+///
+/// Attempt `n` (0-based) pays `tip * tip_multiplier^n + tip_step_sol * n` and
+/// `priority_fee + priority_fee_step * n`, so every attempt is a distinct
+/// transaction; attempts repeating an earlier tip and fee are not sent.
+/// Since all attempts share the same durable nonce, at most one lands.
+#[derive(Debug, Clone)]
+pub struct TipEscalation {
+    pub tip_step_sol: f64,
+    pub tip_multiplier: f64,
+    pub max_tip_sol: Option<f64>,
+    pub priority_fee_step: u64,
+    pub max_priority_fee_micro_lamport: Option<u64>,
+    /// Delay between waves, attempt `n` is launched after `n * wave_delay_ms`
+    pub wave_delay_ms: u64,
+}
+
+impl Default for TipEscalation {
+    fn default() -> Self {
+        Self {
+            tip_step_sol: 0.0,
+            tip_multiplier: 1.0,
+            max_tip_sol: None,
+            priority_fee_step: 0,
+            max_priority_fee_micro_lamport: None,
+            wave_delay_ms: 0,
+        }
+    }
+}
+
+impl TipEscalation {
+    pub fn tips_for_attempt(&self, base: &Tips, attempt: u32) -> Tips {
+        let mut tips = base.clone();
+
+        let mut tip_sol_amount = base.tip_sol_amount * self.tip_multiplier.powi(attempt as i32)
+            + self.tip_step_sol * attempt as f64;
+        if let Some(max_tip_sol) = self.max_tip_sol {
+            tip_sol_amount = tip_sol_amount.min(max_tip_sol);
+        }
+        tips.tip_sol_amount = tip_sol_amount;
+
+        if base.priority_fee_micro_lamport.is_some() || self.priority_fee_step > 0 {
+            let mut priority_fee = base
+                .priority_fee_micro_lamport
+                .unwrap_or(0)
+                .saturating_add(self.priority_fee_step.saturating_mul(attempt as u64));
+            if let Some(max_priority_fee) = self.max_priority_fee_micro_lamport {
+                priority_fee = priority_fee.min(max_priority_fee);
+            }
+            tips.priority_fee_micro_lamport = Some(priority_fee);
+        }
+
+        tips
+    }
+
+    /// Attempts below `retry_count` paying a tip and priority fee no earlier attempt pays.
+    /// The others (e.g. every attempt once `max_tip_sol` caps the tip) would be the same
+    /// transaction, with the same signature, sent again.
+    pub fn distinct_attempts(&self, base: &Tips, retry_count: u32, min_tip: f64) -> Vec<u32> {
+        let mut seen = HashSet::new();
+        (0..retry_count)
+            .filter(|&attempt| {
+                let tips = self.tips_for_attempt(base, attempt);
+                seen.insert((tips.tip_lamports(min_tip), tips.compute_unit_price()))
+            })
+            .collect()
+    }
+
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.wave_delay_ms.saturating_mul(attempt as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Tips {
+        Tips {
+            tip_sol_amount: 0.001,
+            priority_fee_micro_lamport: Some(1_000),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn default_escalation_repeats_the_base_tips() {
+        let escalation = TipEscalation::default();
+        for attempt in 0..3 {
+            let tips = escalation.tips_for_attempt(&base(), attempt);
+            assert_eq!(tips.tip_sol_amount, 0.001);
+            assert_eq!(tips.priority_fee_micro_lamport, Some(1_000));
+            assert_eq!(escalation.delay_for_attempt(attempt), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn tip_grows_by_multiplier_and_step() {
+        let escalation = TipEscalation {
+            tip_step_sol: 0.0005,
+            tip_multiplier: 2.0,
+            ..Default::default()
+        };
+        let tip = |attempt| escalation.tips_for_attempt(&base(), attempt).tip_sol_amount;
+
+        assert!((tip(0) - 0.001).abs() < 1e-12);
+        assert!((tip(1) - 0.0025).abs() < 1e-12);
+        assert!((tip(2) - 0.005).abs() < 1e-12);
+    }
+
+    #[test]
+    fn escalation_is_capped() {
+        let escalation = TipEscalation {
+            tip_multiplier: 10.0,
+            max_tip_sol: Some(0.05),
+            priority_fee_step: 10_000,
+            max_priority_fee_micro_lamport: Some(15_000),
+            ..Default::default()
+        };
+        let tips = escalation.tips_for_attempt(&base(), 3);
+
+        assert_eq!(tips.tip_sol_amount, 0.05);
+        assert_eq!(tips.priority_fee_micro_lamport, Some(15_000));
+    }
+
+    #[test]
+    fn priority_fee_step_applies_without_a_base_fee() {
+        let escalation = TipEscalation {
+            priority_fee_step: 500,
+            ..Default::default()
+        };
+        let base = Tips {
+            priority_fee_micro_lamport: None,
+            ..base()
+        };
+
+        assert_eq!(
+            escalation
+                .tips_for_attempt(&base, 0)
+                .priority_fee_micro_lamport,
+            Some(0)
+        );
+        assert_eq!(
+            escalation
+                .tips_for_attempt(&base, 2)
+                .priority_fee_micro_lamport,
+            Some(1_000)
+        );
+        assert_eq!(
+            TipEscalation::default()
+                .tips_for_attempt(&base, 2)
+                .priority_fee_micro_lamport,
+            None
+        );
+    }
+
+    #[test]
+    fn waves_are_spaced_by_wave_delay() {
+        let escalation = TipEscalation {
+            wave_delay_ms: 150,
+            ..Default::default()
+        };
+
+        assert_eq!(escalation.delay_for_attempt(0), Duration::ZERO);
+        assert_eq!(escalation.delay_for_attempt(3), Duration::from_millis(450));
+        assert_eq!(
+            TipEscalation {
+                wave_delay_ms: u64::MAX,
+                ..Default::default()
+            }
+            .delay_for_attempt(2),
+            Duration::from_millis(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn identical_attempts_are_dropped() {
+        assert_eq!(
+            TipEscalation::default().distinct_attempts(&base(), 3, 0.0),
+            vec![0]
+        );
+
+        let capped = TipEscalation {
+            tip_step_sol: 0.001,
+            max_tip_sol: Some(0.002),
+            ..Default::default()
+        };
+        assert_eq!(capped.distinct_attempts(&base(), 4, 0.0), vec![0, 1]);
+
+        // Below the relayer minimum every tip is raised to the same amount
+        let raised = TipEscalation {
+            tip_step_sol: 0.001,
+            ..Default::default()
+        };
+        assert_eq!(raised.distinct_attempts(&base(), 3, 0.01), vec![0]);
+        assert_eq!(raised.distinct_attempts(&base(), 3, 0.0), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,292 @@
+use std::{
+    collections::{HashMap, hash_map::DefaultHasher},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::time::sleep;
+
+use crate::*;
+
+pub const RATE_LIMIT_QUEUE_MS: u64 = 250;
+
+/// What a send does when its API key is out of tokens
+#[derive(Debug, Clone)]
+pub enum ExhaustionPolicy {
+    /// Wait for a token, rejecting the send if none comes within `max_wait`
+    Queue { max_wait: Duration },
+    /// Reject the send
+    Drop,
+    /// Before launch, swap the relayer for one of `spares` or a relayer routing left
+    /// out that has capacity. Sends that still find no token are rejected.
+    Spill { spares: Vec<ServiceConfig> },
+}
+
+impl Default for ExhaustionPolicy {
+    fn default() -> Self {
+        Self::Queue {
+            max_wait: Duration::from_millis(RATE_LIMIT_QUEUE_MS),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitExhausted {
+    pub service_name: &'static str,
+    /// Until the next token
+    pub wait: Duration,
+}
+
+impl fmt::Display for RateLimitExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rate limit exhausted, next request allowed in {:?}",
+            self.service_name, self.wait
+        )
+    }
+}
+
+impl std::error::Error for RateLimitExhausted {}
+
+/// Keys are hashed so they never show up in logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LimiterKey {
+    service_name: &'static str,
+    api_key_hash: u64,
+}
+
+/// Token buckets per relayer and API key. Relayers without a configured limit are
+/// unthrottled but still back off when they answer 429.
+#[derive(Debug, Default)]
+pub struct RateLimiters {
+    /// Per service name, each API key of the service gets its own bucket
+    pub limits: HashMap<&'static str, RateLimit>,
+    pub on_exhausted: ExhaustionPolicy,
+    buckets: Mutex<HashMap<LimiterKey, TokenBucket>>,
+}
+
+impl RateLimiters {
+    pub fn new(on_exhausted: ExhaustionPolicy) -> Self {
+        Self {
+            limits: HashMap::new(),
+            on_exhausted,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_limit(mut self, service_name: &'static str, limit: RateLimit) -> Self {
+        self.limits.insert(service_name, limit);
+        self
+    }
+
+    /// Runs `f` on the bucket of `service`'s API key
+    fn with_bucket<T>(&self, service: &ServiceConfig, f: impl FnOnce(&mut TokenBucket) -> T) -> T {
+        let mut hasher = DefaultHasher::new();
+        service.client.api_key().hash(&mut hasher);
+        let key = LimiterKey {
+            service_name: service.name,
+            api_key_hash: hasher.finish(),
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key).or_insert_with(|| {
+            let limit = self.limits.get(service.name).copied().unwrap_or(RateLimit {
+                requests_per_sec: f64::from(u32::MAX),
+                burst: u32::MAX,
+            });
+            TokenBucket::new(limit)
+        });
+        f(bucket)
+    }
+
+    pub fn has_capacity(&self, service: &ServiceConfig) -> bool {
+        self.with_bucket(service, |bucket| bucket.wait_time().is_zero())
+    }
+
+    /// Takes a token for one send to `service`, queueing per `on_exhausted`
+    pub async fn acquire(&self, service: &ServiceConfig) -> Result<(), RateLimitExhausted> {
+        let max_wait = match &self.on_exhausted {
+            ExhaustionPolicy::Queue { max_wait } => *max_wait,
+            ExhaustionPolicy::Drop | ExhaustionPolicy::Spill { .. } => Duration::ZERO,
+        };
+        let mut waited = Duration::ZERO;
+
+        loop {
+            let wait = match self.with_bucket(service, |bucket| bucket.try_acquire()) {
+                Ok(()) => return Ok(()),
+                Err(wait) => wait,
+            };
+            if waited.saturating_add(wait) > max_wait {
+                return Err(RateLimitExhausted {
+                    service_name: service.name,
+                    wait,
+                });
+            }
+            sleep(wait).await;
+            waited += wait;
+        }
+    }
+
+    /// Backs the API key off when the relayer answered 429
+    pub fn record_result(&self, service: &ServiceConfig, result: &SubmissionResult) {
+        if let Some(rate_limited) = &result.rate_limited {
+            let backoff = rate_limited.backoff();
+            eprintln!(
+                "[{}] ⏳ Rate limited, backing off for {:?}",
+                service.name, backoff
+            );
+            self.with_bucket(service, |bucket| bucket.back_off(backoff));
+        }
+    }
+
+    /// Swaps relayers out of tokens for spare ones with capacity under `Spill`.
+    /// `left_out` are relayers the routing policy did not pick.
+    pub fn spill(
+        &self,
+        services: Vec<ServiceConfig>,
+        left_out: Vec<ServiceConfig>,
+    ) -> Vec<ServiceConfig> {
+        let ExhaustionPolicy::Spill { spares } = &self.on_exhausted else {
+            return services;
+        };
+
+        let is_same = |a: &ServiceConfig, b: &ServiceConfig| {
+            a.name == b.name && a.client.region() == b.client.region()
+        };
+        let mut spares: Vec<ServiceConfig> = spares.iter().cloned().chain(left_out).collect();
+        spares.retain(|spare| !services.iter().any(|service| is_same(service, spare)));
+
+        services
+            .into_iter()
+            .map(|service| {
+                if self.has_capacity(&service) {
+                    return service;
+                }
+                match spares.iter().position(|spare| self.has_capacity(spare)) {
+                    Some(index) => {
+                        let spare = spares.swap_remove(index);
+                        println!(
+                            "↪️ Spilling {} ({}) to {} ({}), rate limit exhausted",
+                            service.name,
+                            service.client.region(),
+                            spare.name,
+                            spare.client.region()
+                        );
+                        spare
+                    }
+                    None => service,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ultra_submit::ultra_submit_test_support::jito_service;
+
+    fn one_per_sec() -> RateLimit {
+        RateLimit {
+            requests_per_sec: 1.0,
+            burst: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn drop_rejects_once_the_key_is_out_of_tokens() {
+        let limiters = RateLimiters::new(ExhaustionPolicy::Drop).with_limit("Jito", one_per_sec());
+        let service = jito_service("Jito", 1, Some("key-a"));
+
+        assert!(limiters.acquire(&service).await.is_ok());
+        let exhausted = limiters.acquire(&service).await.unwrap_err();
+        assert_eq!(exhausted.service_name, "Jito");
+        assert!(exhausted.wait > Duration::ZERO);
+
+        // Another API key of the same relayer has its own bucket
+        assert!(
+            limiters
+                .acquire(&jito_service("Jito", 1, Some("key-b")))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn queue_waits_for_a_token_within_max_wait() {
+        let fast = RateLimit {
+            requests_per_sec: 50.0,
+            burst: 1,
+        };
+        let limiters = RateLimiters::new(ExhaustionPolicy::Queue {
+            max_wait: Duration::from_millis(100),
+        })
+        .with_limit("Jito", fast);
+        let service = jito_service("Jito", 1, None);
+
+        assert!(limiters.acquire(&service).await.is_ok());
+        assert!(limiters.acquire(&service).await.is_ok());
+
+        let limiters = RateLimiters::new(ExhaustionPolicy::Queue {
+            max_wait: Duration::from_millis(100),
+        })
+        .with_limit("Jito", one_per_sec());
+        assert!(limiters.acquire(&service).await.is_ok());
+        assert!(limiters.acquire(&service).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unconfigured_relayers_only_back_off_on_429() {
+        let limiters = RateLimiters::new(ExhaustionPolicy::Drop);
+        let service = jito_service("Jito", 1, None);
+        for _ in 0..100 {
+            assert!(limiters.acquire(&service).await.is_ok());
+        }
+
+        let result = SubmissionResult {
+            service_name: service.name.to_string(),
+            region: service.client.region().to_string(),
+            attempt: 0,
+            success: false,
+            latency_ms: 0.0,
+            build_ms: 0.0,
+            signature: None,
+            error: Some("rate limited".to_string()),
+            rate_limited: Some(RateLimited {
+                retry_after: Some(Duration::from_secs(5)),
+            }),
+        };
+        limiters.record_result(&service, &result);
+        assert!(!limiters.has_capacity(&service));
+        assert!(limiters.acquire(&service).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn spill_swaps_exhausted_relayers_for_spares() {
+        let limiters = RateLimiters::new(ExhaustionPolicy::Spill {
+            spares: vec![jito_service("Spare", 2, None)],
+        })
+        .with_limit("Jito", one_per_sec());
+        let exhausted = jito_service("Jito", 1, None);
+        let healthy = jito_service("Other", 3, None);
+        limiters.acquire(&exhausted).await.unwrap();
+
+        let spilled = limiters.spill(
+            vec![exhausted.clone(), healthy.clone()],
+            vec![jito_service("LeftOut", 4, None)],
+        );
+        let names: Vec<&str> = spilled.iter().map(|service| service.name).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"Other"));
+        assert!(names.contains(&"Spare") || names.contains(&"LeftOut"));
+        assert!(!names.contains(&"Jito"));
+
+        // Other policies never swap relayers
+        let limiters = RateLimiters::new(ExhaustionPolicy::Drop).with_limit("Jito", one_per_sec());
+        limiters.acquire(&exhausted).await.unwrap();
+        let kept = limiters.spill(vec![exhausted], vec![healthy]);
+        assert_eq!(kept[0].name, "Jito");
+    }
+}
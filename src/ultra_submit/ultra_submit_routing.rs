@@ -0,0 +1,365 @@
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::*;
+
+pub const ROUTING_WINDOW_SECS: u64 = 900;
+pub const DEFAULT_FAN_OUT: usize = 3;
+pub const DEFAULT_EXPLORATION_RATE: f64 = 0.1;
+
+/// Chooses which relayers each submission fans out to from their observed landing rate
+/// per lamport, an upper confidence bound favouring relayers with few samples.
+/// Feed it the same `RelayerStats` given to `UltraSubmitOptions::stats`.
+#[derive(Debug)]
+pub struct RoutingPolicy {
+    pub stats: Arc<RelayerStats>,
+    /// Stats window the scores are computed over
+    pub window: Duration,
+    /// Best scoring relayers sent to every submission, at most with `target_landing_probability`
+    pub fan_out: usize,
+    /// Stop adding relayers once the estimated chance that at least one lands reaches this
+    pub target_landing_probability: Option<f64>,
+    /// Share of submissions that also try relayers outside the best `fan_out`
+    pub exploration_rate: f64,
+    /// Extra relayers added to an exploring submission
+    pub exploration_slots: usize,
+    /// Relayers accepting less than this are skipped once they have `min_samples`
+    pub min_acceptance_rate: f64,
+    pub min_samples: u64,
+    rng: Mutex<XorShift>,
+}
+
+impl RoutingPolicy {
+    pub fn new(stats: Arc<RelayerStats>) -> Self {
+        Self {
+            stats,
+            window: Duration::from_secs(ROUTING_WINDOW_SECS),
+            fan_out: DEFAULT_FAN_OUT,
+            target_landing_probability: None,
+            exploration_rate: DEFAULT_EXPLORATION_RATE,
+            exploration_slots: 1,
+            min_acceptance_rate: 0.5,
+            min_samples: 10,
+            rng: Mutex::new(XorShift::from_time()),
+        }
+    }
+
+    pub fn with_fan_out(mut self, fan_out: usize) -> Self {
+        self.fan_out = fan_out;
+        self
+    }
+
+    /// Sends each submission to as few relayers as reach `target`, up to `fan_out`
+    pub fn with_target_landing_probability(mut self, target: f64) -> Self {
+        self.target_landing_probability = Some(target);
+        self
+    }
+
+    pub fn with_exploration(mut self, exploration_rate: f64, exploration_slots: usize) -> Self {
+        self.exploration_rate = exploration_rate;
+        self.exploration_slots = exploration_slots;
+        self
+    }
+
+    /// Whether `service` has been accepting submissions and its breaker, if any, is not open
+    pub fn is_healthy(&self, service: &ServiceConfig, breakers: Option<&CircuitBreakers>) -> bool {
+        if let Some(breakers) = breakers
+            && !breakers.is_available(service)
+        {
+            return false;
+        }
+
+        self.stats
+            .relayer(service.name, service.client.region(), self.window)
+            .is_none_or(|snapshot| {
+                snapshot.submissions < self.min_samples
+                    || snapshot.acceptance_rate >= self.min_acceptance_rate
+            })
+    }
+
+    /// Laplace smoothed landing rate, 0.5 for a relayer never sent to
+    pub fn landing_rate(&self, service: &ServiceConfig) -> f64 {
+        let (calls, landed) = self
+            .stats
+            .relayer(service.name, service.client.region(), self.window)
+            .map_or((0, 0), |snapshot| (snapshot.submit_calls, snapshot.landed));
+        (landed as f64 + 1.0) / (calls as f64 + 2.0)
+    }
+
+    /// Expected landings per lamport, optimistic by the confidence bound.
+    /// `total_calls` is the number of calls across every candidate.
+    pub fn score(&self, service: &ServiceConfig, total_calls: u64) -> f64 {
+        let snapshot = self
+            .stats
+            .relayer(service.name, service.client.region(), self.window);
+        let (calls, cost) = match &snapshot {
+            Some(snapshot) => (snapshot.submit_calls, snapshot.cost_per_landed_lamports),
+            None => (0, None),
+        };
+
+        // Landing rate plus the UCB1 exploration bonus
+        let landing_rate = self.landing_rate(service);
+        let bonus = (2.0 * ((total_calls + 1) as f64).ln() / (calls + 1) as f64).sqrt();
+        let cost_lamports = cost
+            .unwrap_or(service.client.min_tip() * LAMPORTS_PER_SOL as f64)
+            .max(1.0);
+
+        (landing_rate + bonus) / cost_lamports
+    }
+
+    /// Relayers to send this submission to, best first. `ultra_submit` passes
+    /// `UltraSubmitOptions::breakers` so open breakers are routed around.
+    pub fn select(
+        &self,
+        candidates: Vec<ServiceConfig>,
+        breakers: Option<&CircuitBreakers>,
+    ) -> Vec<ServiceConfig> {
+        let (healthy, unhealthy): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|service| self.is_healthy(service, breakers));
+        // Never route to nothing, fall back to the unhealthy relayers
+        let candidates = if healthy.is_empty() {
+            unhealthy
+        } else {
+            healthy
+        };
+
+        let total_calls: u64 = candidates
+            .iter()
+            .filter_map(|service| {
+                self.stats
+                    .relayer(service.name, service.client.region(), self.window)
+            })
+            .map(|snapshot| snapshot.submit_calls)
+            .sum();
+
+        let mut scored: Vec<(f64, ServiceConfig)> = candidates
+            .into_iter()
+            .map(|service| (self.score(&service, total_calls), service))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        let max_fan_out = self.fan_out.max(1).min(scored.len());
+        let fan_out = match self.target_landing_probability {
+            Some(target) => {
+                let mut miss_probability = 1.0;
+                scored
+                    .iter()
+                    .take(max_fan_out)
+                    .position(|(_, service)| {
+                        miss_probability *= 1.0 - self.landing_rate(service);
+                        1.0 - miss_probability >= target
+                    })
+                    .map_or(max_fan_out, |index| index + 1)
+            }
+            None => max_fan_out,
+        };
+        let mut rest: Vec<ServiceConfig> = scored
+            .split_off(fan_out)
+            .into_iter()
+            .map(|(_, service)| service)
+            .collect();
+        let mut selected: Vec<ServiceConfig> =
+            scored.into_iter().map(|(_, service)| service).collect();
+
+        let mut rng = self.rng.lock().unwrap();
+        if !rest.is_empty() && rng.next_f64() < self.exploration_rate {
+            for _ in 0..self.exploration_slots.min(rest.len()) {
+                let index = (rng.next_u64() % rest.len() as u64) as usize;
+                let explored = rest.swap_remove(index);
+                println!(
+                    "🧭 Exploring {} ({})",
+                    explored.name,
+                    explored.client.region()
+                );
+                selected.push(explored);
+            }
+        }
+
+        selected
+    }
+}
+
+/// xorshift64*, enough randomness for picking relayers to explore
+#[derive(Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        // The state must never be zero
+        Self(nanos | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ultra_submit::ultra_submit_test_support::jito_service;
+    use solana_sdk::signature::Signature;
+
+    /// `calls` ultra_submit calls sent to `service`, `landed` of them landing for `fee_lamports`
+    fn record_calls(
+        stats: &RelayerStats,
+        service: &ServiceConfig,
+        calls: u64,
+        landed: u64,
+        fee_lamports: u64,
+    ) {
+        let signature = Signature::default();
+        for _ in 0..calls {
+            let ledger = SubmissionLedger::new();
+            ledger.record(
+                signature,
+                SubmissionRecord {
+                    service_name: service.name,
+                    region: service.client.region(),
+                    attempt: 0,
+                    fee_lamports,
+                    first_sent_at: None,
+                },
+            );
+            ledger.mark_sent(&signature, service.name, service.client.region(), 0);
+            stats.record_sends(&ledger, None);
+        }
+        for _ in 0..landed {
+            stats.record_landing(&LandingAttribution {
+                signature,
+                service_name: service.name,
+                region: service.client.region(),
+                attempt: 0,
+                fee_lamports,
+                slot: 1,
+                slot_leader: None,
+                time_to_land: None,
+                shared_with: vec![service.name],
+            });
+        }
+    }
+
+    fn policy(stats: &Arc<RelayerStats>) -> RoutingPolicy {
+        RoutingPolicy::new(Arc::clone(stats)).with_exploration(0.0, 0)
+    }
+
+    #[test]
+    fn unseen_relayers_score_their_prior_over_the_min_tip() {
+        let stats = Arc::new(RelayerStats::new());
+        let service = jito_service("Jito", 1, None);
+        let min_tip_lamports = JITO_MIN_TIP * LAMPORTS_PER_SOL as f64;
+
+        // No calls anywhere, no exploration bonus
+        assert_eq!(policy(&stats).score(&service, 0), 0.5 / min_tip_lamports);
+    }
+
+    #[test]
+    fn exploration_bonus_shrinks_with_samples() {
+        let stats = Arc::new(RelayerStats::new());
+        let sampled = jito_service("Jito", 1, None);
+        let fresh = jito_service("Jito", 2, None);
+        record_calls(&stats, &sampled, 98, 49, 10_000);
+        let policy = policy(&stats);
+
+        let landing_rate = 50.0 / 100.0;
+        let bonus = (2.0 * 99f64.ln() / 99.0).sqrt();
+        assert!((policy.score(&sampled, 98) - (landing_rate + bonus) / 10_000.0).abs() < 1e-12);
+
+        // Same prior landing rate, far larger bonus per lamport spent
+        let fresh_bonus = (2.0 * 99f64.ln()).sqrt();
+        let min_tip_lamports = JITO_MIN_TIP * LAMPORTS_PER_SOL as f64;
+        assert!((policy.score(&fresh, 98) - (0.5 + fresh_bonus) / min_tip_lamports).abs() < 1e-12);
+    }
+
+    #[test]
+    fn cheaper_landings_score_higher() {
+        let stats = Arc::new(RelayerStats::new());
+        let cheap = jito_service("Jito", 1, None);
+        let expensive = jito_service("Jito", 2, None);
+        record_calls(&stats, &cheap, 20, 10, 10_000);
+        record_calls(&stats, &expensive, 20, 10, 40_000);
+        let policy = policy(&stats);
+
+        let ratio = policy.score(&cheap, 40) / policy.score(&expensive, 40);
+        assert!((ratio - 4.0).abs() < 1e-9);
+        let selected = policy.with_fan_out(1).select(vec![expensive, cheap], None);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(
+            selected[0].client.region(),
+            jito_service("Jito", 1, None).client.region()
+        );
+    }
+
+    #[test]
+    fn unhealthy_relayers_are_skipped_unless_nothing_else_is_left() {
+        let stats = Arc::new(RelayerStats::new());
+        let healthy = jito_service("Jito", 1, None);
+        let rejecting = jito_service("Jito", 2, None);
+        for _ in 0..10 {
+            stats.record_submission(&SubmissionResult {
+                service_name: rejecting.name.to_string(),
+                region: rejecting.client.region().to_string(),
+                attempt: 0,
+                success: false,
+                latency_ms: 1.0,
+                build_ms: 0.0,
+                signature: Some(Signature::default()),
+                error: Some("rejected".to_string()),
+                rate_limited: None,
+            });
+        }
+        let policy = policy(&stats);
+
+        assert!(!policy.is_healthy(&rejecting, None));
+        let selected = policy.select(vec![rejecting.clone(), healthy.clone()], None);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].client.region(), healthy.client.region());
+
+        // Never route to nothing
+        let selected = policy.select(vec![rejecting.clone()], None);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].client.region(), rejecting.client.region());
+    }
+
+    #[test]
+    fn target_landing_probability_adapts_the_fan_out() {
+        let stats = Arc::new(RelayerStats::new());
+        let services: Vec<ServiceConfig> = (1..=4)
+            .map(|region| jito_service("Jito", region, None))
+            .collect();
+        for service in &services {
+            // Landing rate (80 + 1) / (100 + 2), about 0.79
+            record_calls(&stats, service, 100, 80, 10_000);
+        }
+        let policy = policy(&stats).with_fan_out(4);
+
+        // 1 - 0.21^2 is about 0.956
+        let selected = policy
+            .with_target_landing_probability(0.95)
+            .select(services.clone(), None);
+        assert_eq!(selected.len(), 2);
+
+        // Unreachable targets stop at `fan_out`
+        let policy = RoutingPolicy::new(Arc::clone(&stats))
+            .with_exploration(0.0, 0)
+            .with_fan_out(3)
+            .with_target_landing_probability(1.0);
+        assert_eq!(policy.select(services, None).len(), 3);
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+/// Relayers that start sending `after_ms` after launch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleTier {
+    pub after_ms: u64,
+    pub services: Vec<&'static str>,
+}
+
+/// Periodic resends of the same signed transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResendPolicy {
+    pub interval_ms: u64,
+    /// Stop after this long, or earlier once a blockhash known to the provider expires
+    pub max_duration_ms: u64,
+}
+
+/// When each relayer is sent to, relative to launch. Later tiers and resends stop as soon
+/// as a signature is confirmed or the blockhash expires, so `ultra_submit` only runs a
+/// timed schedule with `CompletionPolicy::FirstConfirmed`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubmissionSchedule {
+    /// Relayers not listed in any tier are sent to immediately
+    pub tiers: Vec<ScheduleTier>,
+    pub resend: Option<ResendPolicy>,
+}
+
+impl SubmissionSchedule {
+    /// `primary` immediately, `secondary` after `secondary_after_ms`
+    pub fn hedged(
+        primary: Vec<&'static str>,
+        secondary: Vec<&'static str>,
+        secondary_after_ms: u64,
+    ) -> Self {
+        Self {
+            tiers: vec![
+                ScheduleTier {
+                    after_ms: 0,
+                    services: primary,
+                },
+                ScheduleTier {
+                    after_ms: secondary_after_ms,
+                    services: secondary,
+                },
+            ],
+            resend: None,
+        }
+    }
+
+    pub fn with_resend(mut self, interval_ms: u64, max_duration_ms: u64) -> Self {
+        self.resend = Some(ResendPolicy {
+            interval_ms,
+            max_duration_ms,
+        });
+        self
+    }
+
+    /// Delays some relayers or resends, which needs a confirmation to stop on
+    pub fn is_timed(&self) -> bool {
+        self.resend.is_some() || self.tiers.iter().any(|tier| tier.after_ms > 0)
+    }
+
+    /// Start delay of `service_name`, the earliest tier listing it
+    pub fn delay_for(&self, service_name: &str) -> Duration {
+        let after_ms = self
+            .tiers
+            .iter()
+            .filter(|tier| tier.services.contains(&service_name))
+            .map(|tier| tier.after_ms)
+            .min()
+            .unwrap_or(0);
+        Duration::from_millis(after_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_delays_and_resends_make_a_schedule_timed() {
+        assert!(!SubmissionSchedule::default().is_timed());
+        assert!(!SubmissionSchedule::hedged(vec!["Jito"], vec!["Nozomi"], 0).is_timed());
+
+        let hedged = SubmissionSchedule::hedged(vec!["Jito"], vec!["Nozomi"], 200);
+        assert!(hedged.is_timed());
+        assert_eq!(hedged.delay_for("Jito"), Duration::ZERO);
+        assert_eq!(hedged.delay_for("Nozomi"), Duration::from_millis(200));
+        assert_eq!(hedged.delay_for("Helius"), Duration::ZERO);
+
+        assert!(
+            SubmissionSchedule::default()
+                .with_resend(100, 1_000)
+                .is_timed()
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+pub const STATS_BUCKET_SECS: u64 = 60;
+pub const STATS_RETENTION_SECS: u64 = 3_600;
+
+/// Latencies above this are clamped, in microseconds
+const MAX_LATENCY_US: u64 = 60_000_000;
+const LATENCY_SIGNIFICANT_DIGITS: u8 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct RelayerKey {
+    pub service_name: String,
+    pub region: String,
+}
+
+struct StatsBucket {
+    started_at: Instant,
+    submissions: u64,
+    accepted: u64,
+    /// `ultra_submit` calls that sent the relayer a transaction
+    submit_calls: u64,
+    landed: u64,
+    landed_fee_lamports: u64,
+    /// Network latency of accepted submissions, in microseconds
+    latency_us: Histogram<u64>,
+}
+
+impl StatsBucket {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            submissions: 0,
+            accepted: 0,
+            submit_calls: 0,
+            landed: 0,
+            landed_fee_lamports: 0,
+            latency_us: Histogram::new_with_bounds(1, MAX_LATENCY_US, LATENCY_SIGNIFICANT_DIGITS)
+                .expect("valid histogram bounds"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct LatencyPercentiles {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// One relayer's performance over a window
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RelayerSnapshot {
+    pub service_name: String,
+    pub region: String,
+    pub window_secs: u64,
+    pub submissions: u64,
+    pub accepted: u64,
+    pub acceptance_rate: f64,
+    pub submit_calls: u64,
+    pub landed: u64,
+    /// Landed over `submit_calls`, at most one transaction of a call lands
+    pub landing_rate: f64,
+    pub latency: Option<LatencyPercentiles>,
+    /// Fee and tip paid per transaction this relayer landed
+    pub cost_per_landed_lamports: Option<f64>,
+}
+
+/// Long-lived per relayer and region statistics, kept in time buckets so any
+/// window up to `retention` can be queried
+pub struct RelayerStats {
+    pub bucket_width: Duration,
+    pub retention: Duration,
+    relayers: RwLock<HashMap<RelayerKey, VecDeque<StatsBucket>>>,
+}
+
+impl std::fmt::Debug for RelayerStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RelayerStats")
+            .field("bucket_width", &self.bucket_width)
+            .field("retention", &self.retention)
+            .field("relayers", &self.relayers.read().unwrap().len())
+            .finish()
+    }
+}
+
+impl Default for RelayerStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RelayerStats {
+    pub fn new() -> Self {
+        Self {
+            bucket_width: Duration::from_secs(STATS_BUCKET_SECS),
+            retention: Duration::from_secs(STATS_RETENTION_SECS),
+            relayers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_buckets(mut self, bucket_width: Duration, retention: Duration) -> Self {
+        self.bucket_width = bucket_width;
+        self.retention = retention;
+        self
+    }
+
+    /// Applies `update` to the current bucket of `key`, dropping buckets past retention
+    fn update(&self, key: RelayerKey, update: impl FnOnce(&mut StatsBucket)) {
+        let mut relayers = self.relayers.write().unwrap();
+        let buckets = relayers.entry(key).or_default();
+
+        while buckets
+            .front()
+            .is_some_and(|bucket| bucket.started_at.elapsed() > self.retention)
+        {
+            buckets.pop_front();
+        }
+        if buckets
+            .back()
+            .is_none_or(|bucket| bucket.started_at.elapsed() >= self.bucket_width)
+        {
+            buckets.push_back(StatsBucket::new());
+        }
+
+        update(buckets.back_mut().expect("bucket was just pushed"));
+    }
+
+    /// Counts a result sent to the relayer. Rejections before sending (budget, blockhash,
+    /// rate limiter, build errors) never reached it and are skipped.
+    pub fn record_submission(&self, result: &SubmissionResult) {
+        if result.signature.is_none() {
+            return;
+        }
+
+        let key = RelayerKey {
+            service_name: result.service_name.clone(),
+            region: result.region.clone(),
+        };
+        self.update(key, |bucket| {
+            bucket.submissions += 1;
+            if result.success {
+                bucket.accepted += 1;
+                bucket
+                    .latency_us
+                    .saturating_record((result.latency_ms * 1000.0).max(1.0) as u64);
+            }
+        });
+    }
+
+    /// Counts each relayer that was sent a transaction, and the landing if any
+    pub fn record_sends(&self, ledger: &SubmissionLedger, landing: Option<&LandingAttribution>) {
+        let mut sent = HashSet::new();
+        for (_, record) in ledger.records() {
+            if record.first_sent_at.is_some() {
+                sent.insert((record.service_name, record.region));
+            }
+        }
+
+        for (service_name, region) in sent {
+            let key = RelayerKey {
+                service_name: service_name.to_string(),
+                region: region.to_string(),
+            };
+            self.update(key, |bucket| bucket.submit_calls += 1);
+        }
+        if let Some(landing) = landing {
+            self.record_landing(landing);
+        }
+    }
+
+    /// Counts a landing found after `record_sends`, e.g. by `SubmissionLedger::wait_for_landing`
+    pub fn record_landing(&self, landing: &LandingAttribution) {
+        let key = RelayerKey {
+            service_name: landing.service_name.to_string(),
+            region: landing.region.to_string(),
+        };
+        self.update(key, |bucket| {
+            bucket.landed += 1;
+            bucket.landed_fee_lamports += landing.fee_lamports;
+        });
+    }
+
+    /// Records every result, send and the landing of one `ultra_submit` call
+    pub fn record_outcome(&self, outcome: &UltraSubmitOutcome) {
+        for result in &outcome.results {
+            self.record_submission(result);
+        }
+        self.record_sends(&outcome.ledger, outcome.landing.as_ref());
+    }
+
+    pub fn relayer(
+        &self,
+        service_name: &str,
+        region: &str,
+        window: Duration,
+    ) -> Option<RelayerSnapshot> {
+        let relayers = self.relayers.read().unwrap();
+        relayers
+            .iter()
+            .find(|(key, _)| key.service_name == service_name && key.region == region)
+            .map(|(key, buckets)| snapshot(key, buckets, self.bucket_width, window))
+    }
+
+    /// Every relayer seen, over the last `window`
+    pub fn snapshot(&self, window: Duration) -> Vec<RelayerSnapshot> {
+        let relayers = self.relayers.read().unwrap();
+        let mut snapshots: Vec<RelayerSnapshot> = relayers
+            .iter()
+            .map(|(key, buckets)| snapshot(key, buckets, self.bucket_width, window))
+            .collect();
+        snapshots.sort_by(|a, b| (&a.service_name, &a.region).cmp(&(&b.service_name, &b.region)));
+        snapshots
+    }
+
+    pub fn to_json(&self, window: Duration) -> serde_json::Value {
+        serde_json::to_value(self.snapshot(window)).unwrap_or_default()
+    }
+}
+
+fn snapshot(
+    key: &RelayerKey,
+    buckets: &VecDeque<StatsBucket>,
+    bucket_width: Duration,
+    window: Duration,
+) -> RelayerSnapshot {
+    let mut submissions = 0;
+    let mut accepted = 0;
+    let mut submit_calls = 0;
+    let mut landed = 0;
+    let mut landed_fee_lamports = 0;
+    let mut latency_us =
+        Histogram::<u64>::new_with_bounds(1, MAX_LATENCY_US, LATENCY_SIGNIFICANT_DIGITS)
+            .expect("valid histogram bounds");
+
+    // A bucket counts while any part of it is inside the window
+    for bucket in buckets
+        .iter()
+        .filter(|bucket| bucket.started_at.elapsed().saturating_sub(bucket_width) <= window)
+    {
+        submissions += bucket.submissions;
+        accepted += bucket.accepted;
+        submit_calls += bucket.submit_calls;
+        landed += bucket.landed;
+        landed_fee_lamports += bucket.landed_fee_lamports;
+        let _ = latency_us.add(&bucket.latency_us);
+    }
+
+    let ratio = |part: u64, total: u64| {
+        if total > 0 {
+            part as f64 / total as f64
+        } else {
+            0.0
+        }
+    };
+    let percentile_ms = |quantile: f64| latency_us.value_at_quantile(quantile) as f64 / 1000.0;
+
+    RelayerSnapshot {
+        service_name: key.service_name.clone(),
+        region: key.region.clone(),
+        window_secs: window.as_secs(),
+        submissions,
+        accepted,
+        acceptance_rate: ratio(accepted, submissions),
+        submit_calls,
+        landed,
+        landing_rate: ratio(landed, submit_calls),
+        latency: (!latency_us.is_empty()).then(|| LatencyPercentiles {
+            p50_ms: percentile_ms(0.5),
+            p90_ms: percentile_ms(0.9),
+            p99_ms: percentile_ms(0.99),
+            max_ms: latency_us.max() as f64 / 1000.0,
+        }),
+        cost_per_landed_lamports: (landed > 0).then(|| landed_fee_lamports as f64 / landed as f64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signature;
+
+    fn result(success: bool, latency_ms: f64, signature: Option<Signature>) -> SubmissionResult {
+        SubmissionResult {
+            service_name: "Jito".to_string(),
+            region: "Jito-Amsterdam".to_string(),
+            attempt: 0,
+            success,
+            latency_ms,
+            build_ms: 0.0,
+            signature,
+            error: (!success).then(|| "failed".to_string()),
+            rate_limited: None,
+        }
+    }
+
+    #[test]
+    fn pre_send_rejections_are_not_counted() {
+        let stats = RelayerStats::new();
+        stats.record_submission(&result(false, 0.0, None));
+        assert!(
+            stats
+                .relayer("Jito", "Jito-Amsterdam", Duration::from_secs(60))
+                .is_none()
+        );
+
+        stats.record_submission(&result(true, 10.0, Some(Signature::default())));
+        stats.record_submission(&result(false, 0.0, None));
+        let snapshot = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(snapshot.submissions, 1);
+        assert_eq!(snapshot.acceptance_rate, 1.0);
+    }
+
+    #[test]
+    fn acceptance_rate_and_percentiles() {
+        let stats = RelayerStats::new();
+        for latency_ms in 1..=100 {
+            stats.record_submission(&result(true, latency_ms as f64, Some(Signature::default())));
+        }
+        stats.record_submission(&result(false, 5.0, Some(Signature::default())));
+
+        let snapshot = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(snapshot.submissions, 101);
+        assert_eq!(snapshot.accepted, 100);
+        assert!((snapshot.acceptance_rate - 100.0 / 101.0).abs() < 1e-9);
+
+        // Failed submissions carry no latency
+        let latency = snapshot.latency.unwrap();
+        assert!((latency.p50_ms - 50.0).abs() < 0.1);
+        assert!((latency.p99_ms - 99.0).abs() < 0.1);
+        assert!((latency.max_ms - 100.0).abs() < 0.1);
+        assert_eq!(snapshot.cost_per_landed_lamports, None);
+    }
+
+    #[test]
+    fn buckets_outside_the_window_are_excluded() {
+        let stats =
+            RelayerStats::new().with_buckets(Duration::from_millis(10), Duration::from_secs(60));
+        stats.record_submission(&result(true, 1.0, Some(Signature::default())));
+        std::thread::sleep(Duration::from_millis(50));
+        stats.record_submission(&result(false, 1.0, Some(Signature::default())));
+
+        let recent = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_millis(20))
+            .unwrap();
+        assert_eq!(recent.submissions, 1);
+        assert_eq!(recent.accepted, 0);
+        assert!(recent.latency.is_none());
+
+        let all = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(all.submissions, 2);
+        assert_eq!(all.accepted, 1);
+    }
+
+    #[test]
+    fn buckets_past_retention_are_dropped() {
+        let stats =
+            RelayerStats::new().with_buckets(Duration::from_millis(5), Duration::from_millis(20));
+        stats.record_submission(&result(true, 1.0, Some(Signature::default())));
+        std::thread::sleep(Duration::from_millis(40));
+        stats.record_submission(&result(true, 1.0, Some(Signature::default())));
+
+        let snapshot = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(snapshot.submissions, 1);
+    }
+
+    #[test]
+    fn buckets_partly_inside_the_window_are_included() {
+        let stats =
+            RelayerStats::new().with_buckets(Duration::from_millis(40), Duration::from_secs(60));
+        stats.record_submission(&result(true, 1.0, Some(Signature::default())));
+        std::thread::sleep(Duration::from_millis(30));
+
+        // Started before the window but still open inside it
+        let snapshot = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_millis(10))
+            .unwrap();
+        assert_eq!(snapshot.submissions, 1);
+    }
+
+    #[test]
+    fn late_landings_are_counted_once() {
+        let stats = RelayerStats::new();
+        let ledger = SubmissionLedger::new();
+        let signature = Signature::default();
+        ledger.record(
+            signature,
+            SubmissionRecord {
+                service_name: "Jito",
+                region: "Jito-Amsterdam",
+                attempt: 0,
+                fee_lamports: 5_000,
+                first_sent_at: None,
+            },
+        );
+        ledger.mark_sent(&signature, "Jito", "Jito-Amsterdam", 0);
+
+        stats.record_sends(&ledger, None);
+        stats.record_landing(&LandingAttribution {
+            signature,
+            service_name: "Jito",
+            region: "Jito-Amsterdam",
+            attempt: 0,
+            fee_lamports: 5_000,
+            slot: 1,
+            slot_leader: None,
+            time_to_land: None,
+            shared_with: vec!["Jito"],
+        });
+
+        let snapshot = stats
+            .relayer("Jito", "Jito-Amsterdam", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(snapshot.submit_calls, 1);
+        assert_eq!(snapshot.landed, 1);
+        assert_eq!(snapshot.landing_rate, 1.0);
+        assert_eq!(snapshot.cost_per_landed_lamports, Some(5_000.0));
+    }
+}
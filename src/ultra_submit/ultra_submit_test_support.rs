@@ -0,0 +1,19 @@
+use std::sync::Arc;
+
+use crate::*;
+
+/// Jito client on `JITO_REGIONS[region]` registered as `name`, never contacted by tests
+pub(crate) fn jito_service(
+    name: &'static str,
+    region: usize,
+    auth_key: Option<&str>,
+) -> ServiceConfig {
+    ServiceConfig {
+        name,
+        client: ServiceClient::Jito(Arc::new(Jito {
+            client: reqwest::Client::new(),
+            endpoint: JITO_REGIONS[region].clone(),
+            auth_key: auth_key.map(str::to_string),
+        })),
+    }
+}
@@ -0,0 +1,310 @@
+use solana_address_lookup_table_interface::{program, state::AddressLookupTable};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    account::Account, clock::Slot, commitment_config::CommitmentConfig,
+    message::AddressLookupTableAccount, pubkey::Pubkey,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::{task::JoinHandle, time::sleep};
+
+pub const ALT_REFRESH_INTERVAL_MS: u64 = 30_000;
+
+/// `getMultipleAccounts` accepts at most this many addresses per request
+const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct CachedAlt {
+    pub account: AddressLookupTableAccount,
+    /// `Slot::MAX` while the table is active
+    pub deactivation_slot: Slot,
+    pub last_extended_slot: Slot,
+    /// Slot the account was read at
+    pub fetched_slot: Slot,
+    fetched_at: Instant,
+}
+
+impl CachedAlt {
+    /// Lookup table held by `account`, as read at `slot`
+    fn decode(address: &Pubkey, account: &Account, slot: Slot) -> anyhow::Result<Self> {
+        if account.owner != program::id() {
+            anyhow::bail!("Account {} is not an address lookup table", address);
+        }
+
+        let table = AddressLookupTable::deserialize(&account.data).map_err(|err| {
+            anyhow::anyhow!("Failed to deserialize lookup table {}: {}", address, err)
+        })?;
+
+        // Addresses appended in the current slot can't be looked up until the next one
+        let usable = if table.meta.last_extended_slot >= slot {
+            &table.addresses[..table.meta.last_extended_slot_start_index as usize]
+        } else {
+            &table.addresses[..]
+        };
+
+        Ok(Self {
+            account: AddressLookupTableAccount {
+                key: *address,
+                addresses: usable.to_vec(),
+            },
+            deactivation_slot: table.meta.deactivation_slot,
+            last_extended_slot: table.meta.last_extended_slot,
+            fetched_slot: slot,
+            fetched_at: Instant::now(),
+        })
+    }
+
+    pub fn is_deactivated(&self) -> bool {
+        self.deactivation_slot != Slot::MAX
+    }
+
+    /// Fetched at least `refresh_interval` ago
+    pub fn is_stale(&self, refresh_interval: Duration) -> bool {
+        self.fetched_at.elapsed() >= refresh_interval
+    }
+}
+
+/// Address lookup tables fetched from RPC and kept fresh for the builders
+pub struct AltCache {
+    pub rpc_client: Arc<RpcClient>,
+    pub refresh_interval: Duration,
+    tables: RwLock<HashMap<Pubkey, CachedAlt>>,
+}
+
+impl AltCache {
+    pub fn new(rpc_endpoint: String) -> Self {
+        Self {
+            rpc_client: Arc::new(RpcClient::new_with_commitment(
+                rpc_endpoint,
+                CommitmentConfig::confirmed(),
+            )),
+            refresh_interval: Duration::from_millis(ALT_REFRESH_INTERVAL_MS),
+            tables: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// Fetches `addresses` and replaces their cache entries, dropping closed tables
+    pub async fn fetch(&self, addresses: &[Pubkey]) -> anyhow::Result<()> {
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = self
+                .rpc_client
+                .get_multiple_accounts_with_commitment(chunk, self.rpc_client.commitment())
+                .await?;
+            let slot = response.context.slot;
+
+            for (address, account) in chunk.iter().zip(response.value) {
+                let Some(account) = account else {
+                    eprintln!("Lookup table {} is closed", address);
+                    self.tables.write().unwrap().remove(address);
+                    continue;
+                };
+
+                let cached = CachedAlt::decode(address, &account, slot)?;
+                self.tables.write().unwrap().insert(*address, cached);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lookup tables for `addresses`, refetching missing or stale entries first.
+    /// Fails on unknown or closed tables and skips deactivated ones.
+    pub async fn get(
+        &self,
+        addresses: &[Pubkey],
+    ) -> anyhow::Result<Arc<[AddressLookupTableAccount]>> {
+        let stale: Vec<Pubkey> = {
+            let tables = self.tables.read().unwrap();
+            addresses
+                .iter()
+                .filter(|address| {
+                    tables
+                        .get(address)
+                        .is_none_or(|cached| cached.is_stale(self.refresh_interval))
+                })
+                .copied()
+                .collect()
+        };
+
+        if !stale.is_empty() {
+            self.fetch(&stale).await?;
+        }
+
+        let tables = self.tables.read().unwrap();
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let cached = tables
+                .get(address)
+                .ok_or_else(|| anyhow::anyhow!("Lookup table {} not found", address))?;
+
+            if cached.is_deactivated() {
+                eprintln!(
+                    "Skipping lookup table {} deactivated at slot {}",
+                    address, cached.deactivation_slot
+                );
+                continue;
+            }
+            accounts.push(cached.account.clone());
+        }
+
+        Ok(accounts.into())
+    }
+
+    /// Cached, active lookup tables for `addresses` without touching the RPC
+    pub fn cached(&self, addresses: &[Pubkey]) -> Arc<[AddressLookupTableAccount]> {
+        let tables = self.tables.read().unwrap();
+        addresses
+            .iter()
+            .filter_map(|address| tables.get(address))
+            .filter(|cached| !cached.is_deactivated())
+            .map(|cached| cached.account.clone())
+            .collect()
+    }
+
+    pub fn entry(&self, address: &Pubkey) -> Option<CachedAlt> {
+        self.tables.read().unwrap().get(address).cloned()
+    }
+
+    pub fn invalidate(&self, address: &Pubkey) {
+        self.tables.write().unwrap().remove(address);
+    }
+
+    pub async fn refresh_all(&self) -> anyhow::Result<()> {
+        let addresses: Vec<Pubkey> = self.tables.read().unwrap().keys().copied().collect();
+        self.fetch(&addresses).await
+    }
+
+    /// Refreshes every cached table each `refresh_interval` in the background
+    pub fn spawn_refresh(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                sleep(self.refresh_interval).await;
+                if let Err(err) = self.refresh_all().await {
+                    eprintln!("Lookup table refresh failed: {}", err);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_address_lookup_table_interface::state::LookupTableMeta;
+    use std::borrow::Cow;
+
+    fn table_account(meta: LookupTableMeta, addresses: &[Pubkey]) -> Account {
+        let data = AddressLookupTable {
+            meta,
+            addresses: Cow::Borrowed(addresses),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        Account {
+            lamports: 1,
+            data,
+            owner: program::id(),
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    /// Cache whose RPC is unreachable, so any refetch fails
+    fn cache_with(tables: Vec<CachedAlt>) -> AltCache {
+        let cache = AltCache::new("http://127.0.0.1:1".to_string());
+        for table in tables {
+            cache
+                .tables
+                .write()
+                .unwrap()
+                .insert(table.account.key, table);
+        }
+        cache
+    }
+
+    fn cached(deactivation_slot: Slot) -> CachedAlt {
+        CachedAlt {
+            account: AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![Pubkey::new_unique()],
+            },
+            deactivation_slot,
+            last_extended_slot: 0,
+            fetched_slot: 0,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn addresses_extended_in_the_fetch_slot_are_not_usable_yet() {
+        let address = Pubkey::new_unique();
+        let addresses = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let account = table_account(
+            LookupTableMeta {
+                last_extended_slot: 10,
+                last_extended_slot_start_index: 2,
+                ..Default::default()
+            },
+            &addresses,
+        );
+
+        let same_slot = CachedAlt::decode(&address, &account, 10).unwrap();
+        assert_eq!(same_slot.account.addresses, addresses[..2]);
+        assert!(!same_slot.is_deactivated());
+
+        let next_slot = CachedAlt::decode(&address, &account, 11).unwrap();
+        assert_eq!(next_slot.account.addresses, addresses);
+
+        let not_a_table = Account {
+            owner: Pubkey::new_unique(),
+            ..account
+        };
+        assert!(CachedAlt::decode(&address, &not_a_table, 11).is_err());
+    }
+
+    #[tokio::test]
+    async fn deactivated_tables_are_skipped() {
+        let active = cached(Slot::MAX);
+        let deactivated = cached(42);
+        let addresses = [active.account.key, deactivated.account.key];
+        let cache = cache_with(vec![active.clone(), deactivated]);
+
+        assert_eq!(
+            cache.cached(&addresses).to_vec(),
+            vec![active.account.clone()]
+        );
+        assert_eq!(
+            cache.get(&addresses).await.unwrap().to_vec(),
+            vec![active.account]
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_entries_are_refetched() {
+        let mut table = cached(Slot::MAX);
+        let address = table.account.key;
+        let cache = cache_with(vec![table.clone()]).with_refresh_interval(Duration::from_secs(30));
+
+        // Fresh entries are served without the RPC
+        assert!(cache.get(&[address]).await.is_ok());
+
+        table.fetched_at = Instant::now() - Duration::from_secs(60);
+        assert!(table.is_stale(cache.refresh_interval));
+        cache.tables.write().unwrap().insert(address, table);
+        assert!(cache.get(&[address]).await.is_err());
+        // The cached copy is still served to callers that can't wait on the RPC
+        assert_eq!(cache.cached(&[address]).len(), 1);
+    }
+}
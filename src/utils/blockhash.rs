@@ -0,0 +1,280 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, hash::Hash};
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::{task::JoinHandle, time::sleep};
+
+pub const BLOCKHASH_POLL_INTERVAL_MS: u64 = 1_000;
+pub const DEFAULT_MIN_BLOCKS_REMAINING: u64 = 20;
+
+/// Expired blockhashes are remembered this many blocks longer so `lookup` still flags them
+const EXPIRED_RETENTION_BLOCKS: u64 = 150;
+/// Used to extrapolate the block height between polls
+const BLOCK_TIME_MS: u128 = 400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockhashInfo {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+}
+
+#[derive(Debug, Default)]
+struct BlockhashState {
+    /// Issued blockhashes, newest last
+    history: VecDeque<BlockhashInfo>,
+    block_height: u64,
+    observed_at: Option<Instant>,
+}
+
+/// Recent blockhashes with their expiry, refreshed by polling
+pub struct BlockhashProvider {
+    pub rpc_client: RpcClient,
+    pub poll_interval: Duration,
+    state: RwLock<BlockhashState>,
+}
+
+impl fmt::Debug for BlockhashProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockhashProvider")
+            .field("rpc_url", &self.rpc_client.url())
+            .field("poll_interval", &self.poll_interval)
+            .field("current", &self.current())
+            .finish()
+    }
+}
+
+impl BlockhashProvider {
+    pub fn new(rpc_endpoint: String) -> Self {
+        Self {
+            rpc_client: RpcClient::new_with_commitment(rpc_endpoint, CommitmentConfig::confirmed()),
+            poll_interval: Duration::from_millis(BLOCKHASH_POLL_INTERVAL_MS),
+            state: RwLock::new(BlockhashState::default()),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Fetches the latest blockhash and block height, pruning long-expired blockhashes
+    pub async fn refresh(&self) -> anyhow::Result<BlockhashInfo> {
+        let commitment = self.rpc_client.commitment();
+        let ((blockhash, last_valid_block_height), block_height) = tokio::try_join!(
+            self.rpc_client
+                .get_latest_blockhash_with_commitment(commitment),
+            self.rpc_client.get_block_height_with_commitment(commitment),
+        )?;
+
+        let info = BlockhashInfo {
+            blockhash,
+            last_valid_block_height,
+        };
+        self.observe(info, block_height);
+
+        Ok(info)
+    }
+
+    /// Records a polled blockhash and block height
+    fn observe(&self, info: BlockhashInfo, block_height: u64) {
+        let mut state = self.state.write().unwrap();
+        if state.history.back() != Some(&info) {
+            state.history.push_back(info);
+        }
+        state.block_height = block_height;
+        state.observed_at = Some(Instant::now());
+        state.history.retain(|known| {
+            known.last_valid_block_height + EXPIRED_RETENTION_BLOCKS >= block_height
+        });
+    }
+
+    /// Newest known blockhash, no RPC call
+    pub fn current(&self) -> Option<BlockhashInfo> {
+        self.state.read().unwrap().history.back().copied()
+    }
+
+    /// Expiry info for `blockhash` if it came from this provider
+    pub fn lookup(&self, blockhash: &Hash) -> Option<BlockhashInfo> {
+        self.state
+            .read()
+            .unwrap()
+            .history
+            .iter()
+            .rev()
+            .find(|known| known.blockhash == *blockhash)
+            .copied()
+    }
+
+    /// Last polled block height, extrapolated by the time since the poll
+    pub fn estimated_block_height(&self) -> u64 {
+        let state = self.state.read().unwrap();
+        let elapsed_blocks = state
+            .observed_at
+            .map(|at| at.elapsed().as_millis() / BLOCK_TIME_MS)
+            .unwrap_or(0);
+        state.block_height + elapsed_blocks as u64
+    }
+
+    pub fn blocks_remaining(&self, info: &BlockhashInfo) -> u64 {
+        info.last_valid_block_height
+            .saturating_sub(self.estimated_block_height())
+    }
+
+    pub fn is_expiring(&self, info: &BlockhashInfo, min_blocks_remaining: u64) -> bool {
+        self.blocks_remaining(info) < min_blocks_remaining
+    }
+
+    /// Current blockhash if it has `min_blocks_remaining` left, otherwise a refreshed one
+    pub async fn latest(&self, min_blocks_remaining: u64) -> anyhow::Result<BlockhashInfo> {
+        if let Some(info) = self.current()
+            && !self.is_expiring(&info, min_blocks_remaining)
+        {
+            return Ok(info);
+        }
+
+        let info = self.refresh().await?;
+        if self.is_expiring(&info, min_blocks_remaining) {
+            anyhow::bail!(
+                "Latest blockhash {} has only {} blocks remaining",
+                info.blockhash,
+                self.blocks_remaining(&info)
+            );
+        }
+        Ok(info)
+    }
+
+    /// Polls every `poll_interval` in the background
+    pub fn spawn_polling(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = self.refresh().await {
+                    eprintln!("Blockhash refresh failed: {}", err);
+                }
+                sleep(self.poll_interval).await;
+            }
+        })
+    }
+}
+
+/// What a submission does with a blockhash close to expiry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpiryAction {
+    /// Fail without submitting
+    Refuse,
+    /// Swap in the provider's latest blockhash
+    #[default]
+    Rebuild,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockhashPolicy {
+    pub provider: Arc<BlockhashProvider>,
+    pub min_blocks_remaining: u64,
+    pub on_expiry: ExpiryAction,
+}
+
+impl BlockhashPolicy {
+    pub fn new(provider: Arc<BlockhashProvider>) -> Self {
+        Self {
+            provider,
+            min_blocks_remaining: DEFAULT_MIN_BLOCKS_REMAINING,
+            on_expiry: ExpiryAction::default(),
+        }
+    }
+
+    /// Blockhash to build with: `recent_blockhash` unless it is about to expire.
+    /// Hashes the provider never issued (e.g. durable nonce values) pass through.
+    pub async fn resolve(&self, recent_blockhash: Hash) -> anyhow::Result<Hash> {
+        let Some(info) = self.provider.lookup(&recent_blockhash) else {
+            return Ok(recent_blockhash);
+        };
+
+        if !self.provider.is_expiring(&info, self.min_blocks_remaining) {
+            return Ok(recent_blockhash);
+        }
+
+        match self.on_expiry {
+            ExpiryAction::Refuse => anyhow::bail!(
+                "Blockhash {} has only {} blocks remaining (minimum {})",
+                recent_blockhash,
+                self.provider.blocks_remaining(&info),
+                self.min_blocks_remaining
+            ),
+            ExpiryAction::Rebuild => Ok(self
+                .provider
+                .latest(self.min_blocks_remaining)
+                .await?
+                .blockhash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(seed: u8, last_valid_block_height: u64) -> BlockhashInfo {
+        BlockhashInfo {
+            blockhash: Hash::new_from_array([seed; 32]),
+            last_valid_block_height,
+        }
+    }
+
+    /// Provider that already saw `issued` at `block_height`, never reachable over RPC
+    fn provider(issued: &[BlockhashInfo], block_height: u64) -> Arc<BlockhashProvider> {
+        let provider = BlockhashProvider::new("http://127.0.0.1:1".to_string());
+        for info in issued {
+            provider.observe(*info, block_height);
+        }
+        Arc::new(provider)
+    }
+
+    fn policy(provider: Arc<BlockhashProvider>, on_expiry: ExpiryAction) -> BlockhashPolicy {
+        BlockhashPolicy {
+            on_expiry,
+            ..BlockhashPolicy::new(provider)
+        }
+    }
+
+    #[tokio::test]
+    async fn min_blocks_remaining_is_the_expiry_boundary() {
+        let old = info(1, 100);
+        let provider = provider(&[old], 80);
+
+        assert_eq!(provider.blocks_remaining(&old), 20);
+        assert!(!provider.is_expiring(&old, 20));
+        assert!(provider.is_expiring(&old, 21));
+
+        let mut refuse = policy(Arc::clone(&provider), ExpiryAction::Refuse);
+        refuse.min_blocks_remaining = 20;
+        assert_eq!(refuse.resolve(old.blockhash).await.unwrap(), old.blockhash);
+        refuse.min_blocks_remaining = 21;
+        assert!(refuse.resolve(old.blockhash).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rebuild_swaps_in_the_latest_blockhash() {
+        let old = info(1, 100);
+        let new = info(2, 250);
+        let provider = provider(&[old, new], 90);
+
+        let rebuild = policy(provider, ExpiryAction::Rebuild);
+        assert_eq!(rebuild.resolve(old.blockhash).await.unwrap(), new.blockhash);
+        assert_eq!(rebuild.resolve(new.blockhash).await.unwrap(), new.blockhash);
+    }
+
+    #[tokio::test]
+    async fn unknown_blockhashes_pass_through() {
+        let provider = provider(&[info(1, 100)], 99);
+        let nonce_value = Hash::new_unique();
+
+        for on_expiry in [ExpiryAction::Refuse, ExpiryAction::Rebuild] {
+            let policy = policy(Arc::clone(&provider), on_expiry);
+            assert_eq!(policy.resolve(nonce_value).await.unwrap(), nonce_value);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::Tips;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3_600);
+
+#[derive(Debug, Clone, Default)]
+pub struct BudgetLimits {
+    pub max_tip_lamports_per_tx: Option<u64>,
+    /// Tip + CU price × CU limit
+    pub max_fee_lamports_per_tx: Option<u64>,
+    pub max_lamports_per_minute: Option<u64>,
+    pub max_lamports_per_hour: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BudgetError {
+    TipLimitExceeded {
+        tip_lamports: u64,
+        limit: u64,
+    },
+    FeeLimitExceeded {
+        fee_lamports: u64,
+        limit: u64,
+    },
+    RollingLimitExceeded {
+        window: &'static str,
+        spent_lamports: u64,
+        requested_lamports: u64,
+        limit: u64,
+    },
+}
+
+impl fmt::Display for BudgetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetError::TipLimitExceeded {
+                tip_lamports,
+                limit,
+            } => write!(
+                f,
+                "tip of {} lamports exceeds per-tx limit of {} lamports",
+                tip_lamports, limit
+            ),
+            BudgetError::FeeLimitExceeded {
+                fee_lamports,
+                limit,
+            } => write!(
+                f,
+                "total fee of {} lamports exceeds per-tx limit of {} lamports",
+                fee_lamports, limit
+            ),
+            BudgetError::RollingLimitExceeded {
+                window,
+                spent_lamports,
+                requested_lamports,
+                limit,
+            } => write!(
+                f,
+                "spending {} lamports would exceed the per-{} limit of {} lamports ({} already spent)",
+                requested_lamports, window, limit, spent_lamports
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BudgetError {}
+
+/// Lamports held against the rolling windows by `try_spend` until `settle`d
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetReservation {
+    id: u64,
+    pub lamports: u64,
+}
+
+#[derive(Debug)]
+struct Spend {
+    id: u64,
+    at: Instant,
+    lamports: u64,
+}
+
+/// Spending guardrail shared by every client submitting through it
+#[derive(Debug)]
+pub struct BudgetGuard {
+    pub limits: BudgetLimits,
+    spent: Mutex<VecDeque<Spend>>,
+    next_id: AtomicU64,
+}
+
+impl BudgetGuard {
+    pub fn new(limits: BudgetLimits) -> Self {
+        Self {
+            limits,
+            spent: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Checks the per-tx caps and returns the total fee of the transaction
+    pub fn check_tx(&self, tips: &Tips, min_tip: f64) -> Result<u64, BudgetError> {
+        let tip_lamports = tips.tip_lamports(min_tip);
+        if let Some(limit) = self.limits.max_tip_lamports_per_tx
+            && tip_lamports > limit
+        {
+            return Err(BudgetError::TipLimitExceeded {
+                tip_lamports,
+                limit,
+            });
+        }
+
+        let fee_lamports = tips.total_fee_lamports(min_tip);
+        if let Some(limit) = self.limits.max_fee_lamports_per_tx
+            && fee_lamports > limit
+        {
+            return Err(BudgetError::FeeLimitExceeded {
+                fee_lamports,
+                limit,
+            });
+        }
+
+        Ok(fee_lamports)
+    }
+
+    /// Reserves `lamports` against the rolling windows, or rejects without reserving.
+    /// The reservation counts in full until `settle` lowers it to what was actually paid.
+    pub fn try_spend(&self, lamports: u64) -> Result<BudgetReservation, BudgetError> {
+        let now = Instant::now();
+        let mut spent = self.spent.lock().unwrap();
+
+        while let Some(spend) = spent.front() {
+            if now.duration_since(spend.at) > HOUR {
+                spent.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let windows = [
+            ("minute", MINUTE, self.limits.max_lamports_per_minute),
+            ("hour", HOUR, self.limits.max_lamports_per_hour),
+        ];
+
+        for (window, duration, limit) in windows {
+            let Some(limit) = limit else { continue };
+            let spent_lamports = Self::sum_since(&spent, now, duration);
+            if spent_lamports.saturating_add(lamports) > limit {
+                return Err(BudgetError::RollingLimitExceeded {
+                    window,
+                    spent_lamports,
+                    requested_lamports: lamports,
+                    limit,
+                });
+            }
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        spent.push_back(Spend {
+            id,
+            at: now,
+            lamports,
+        });
+        Ok(BudgetReservation { id, lamports })
+    }
+
+    /// Lowers a reservation to the lamports actually paid, zero when nothing landed.
+    /// Never raises it, and reservations older than the hour window are already gone.
+    pub fn settle(&self, reservation: BudgetReservation, paid_lamports: u64) {
+        let mut spent = self.spent.lock().unwrap();
+        if let Some(spend) = spent.iter_mut().find(|spend| spend.id == reservation.id) {
+            spend.lamports = spend.lamports.min(paid_lamports);
+        }
+    }
+
+    /// Per-tx check followed by recording the fee against the rolling windows
+    pub fn check_and_spend(&self, tips: &Tips, min_tip: f64) -> Result<u64, BudgetError> {
+        let fee_lamports = self.check_tx(tips, min_tip)?;
+        self.try_spend(fee_lamports)?;
+        Ok(fee_lamports)
+    }
+
+    pub fn spent_within(&self, window: Duration) -> u64 {
+        let spent = self.spent.lock().unwrap();
+        Self::sum_since(&spent, Instant::now(), window)
+    }
+
+    fn sum_since(spent: &VecDeque<Spend>, now: Instant, window: Duration) -> u64 {
+        spent
+            .iter()
+            .filter(|spend| now.duration_since(spend.at) <= window)
+            .fold(0u64, |acc, spend| acc.saturating_add(spend.lamports))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guard(per_minute: u64) -> BudgetGuard {
+        BudgetGuard::new(BudgetLimits {
+            max_lamports_per_minute: Some(per_minute),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn rolling_limit_rejects_without_reserving() {
+        let guard = guard(1_000);
+        guard.try_spend(600).unwrap();
+
+        assert_eq!(
+            guard.try_spend(500),
+            Err(BudgetError::RollingLimitExceeded {
+                window: "minute",
+                spent_lamports: 600,
+                requested_lamports: 500,
+                limit: 1_000,
+            })
+        );
+        assert_eq!(guard.spent_within(MINUTE), 600);
+    }
+
+    #[test]
+    fn settling_refunds_the_unpaid_part() {
+        let guard = guard(1_000);
+        let reservation = guard.try_spend(800).unwrap();
+        assert!(guard.try_spend(300).is_err());
+
+        guard.settle(reservation, 200);
+        assert_eq!(guard.spent_within(MINUTE), 200);
+        assert!(guard.try_spend(300).is_ok());
+
+        // Settling never raises a reservation
+        guard.settle(reservation, 10_000);
+        assert_eq!(guard.spent_within(MINUTE), 500);
+    }
+
+    #[test]
+    fn per_tx_caps_are_checked() {
+        let guard = BudgetGuard::new(BudgetLimits {
+            max_tip_lamports_per_tx: Some(1_000),
+            ..Default::default()
+        });
+        let tips = Tips {
+            tip_sol_amount: 0.001,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            guard.check_tx(&tips, 0.0),
+            Err(BudgetError::TipLimitExceeded {
+                tip_lamports: 1_000_000,
+                limit: 1_000,
+            })
+        );
+    }
+}
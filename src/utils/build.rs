@@ -1,119 +1,271 @@
-use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
     bs58,
     hash::Hash,
     instruction::Instruction,
     message::{AddressLookupTableAccount, VersionedMessage, v0::Message},
     pubkey::Pubkey,
-    signature::Keypair,
-    transaction::{Transaction, VersionedTransaction},
+    signature::Signature,
+    signer::Signer,
+    transaction::VersionedTransaction,
 };
+use once_cell::sync::OnceCell;
+use std::future::Future;
 
-pub fn build_v0_bs64(
+use crate::*;
+
+/// Compiles the V0 message every builder signs, with the nonce advance first
+pub fn compile_v0(
     mut ixs: Vec<Instruction>,
     fee_payer: &Pubkey,
-    signers: &Vec<&Keypair>,
     recent_blockhash: Hash,
     nonce_ix: Option<Instruction>,
-    alt: Vec<AddressLookupTableAccount>,
-) -> String {
+    alt: &[AddressLookupTableAccount],
+) -> anyhow::Result<VersionedMessage> {
     if let Some(nonce_instruction) = nonce_ix {
+        if !is_advance_nonce_ix(&nonce_instruction) {
+            anyhow::bail!("Nonce instruction is not an advance_nonce_account instruction");
+        }
+        // The advance must come first, drop a copy already present in `ixs`
+        ixs.retain(|ix| *ix != nonce_instruction);
         ixs.insert(0, nonce_instruction);
     }
 
-    let message: Message = Message::try_compile(fee_payer, &ixs, &alt, recent_blockhash)
-        .expect("Failed to compile message");
-    let versioned_message = VersionedMessage::V0(message);
-    let txn = VersionedTransaction::try_new(versioned_message, signers)
-        .expect("Failed to create transaction");
+    let message = Message::try_compile(fee_payer, &ixs, alt, recent_blockhash)?;
+    Ok(VersionedMessage::V0(message))
+}
 
-    let serialized_tx = bincode::serialize(&txn).expect("Failed to serialize transaction");
-    bs64::encode(&serialized_tx)
+/// A signed V0 transaction with its wire bytes, encoded on first use
+#[derive(Debug, Clone)]
+pub struct BuiltTransaction {
+    pub tx: VersionedTransaction,
+    pub signature: Signature,
+    pub size_bytes: usize,
+    serialized: Vec<u8>,
+    base64: OnceCell<String>,
+    base58: OnceCell<String>,
 }
 
-pub fn build_v0_bs58(
-    mut ixs: Vec<Instruction>,
-    fee_payer: &Pubkey,
-    signers: &Vec<&Keypair>,
-    recent_blockhash: Hash,
-    nonce_ix: Option<Instruction>,
-    alt: Vec<AddressLookupTableAccount>,
-) -> String {
-    if let Some(nonce_instruction) = nonce_ix {
-        ixs.insert(0, nonce_instruction);
+impl BuiltTransaction {
+    pub fn new(tx: VersionedTransaction) -> anyhow::Result<Self> {
+        let serialized = bincode::serialize(&tx)?;
+        let signature = *tx
+            .signatures
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("Transaction has no signatures"))?;
+
+        Ok(Self {
+            tx,
+            signature,
+            size_bytes: serialized.len(),
+            serialized,
+            base64: OnceCell::new(),
+            base58: OnceCell::new(),
+        })
     }
 
-    let message: Message = Message::try_compile(fee_payer, &ixs, &alt, recent_blockhash)
-        .expect("Failed to compile message");
-    let versioned_message = VersionedMessage::V0(message);
-    let txn = VersionedTransaction::try_new(versioned_message, signers)
-        .expect("Failed to create transaction");
+    pub fn serialized(&self) -> &[u8] {
+        &self.serialized
+    }
+
+    pub fn base64(&self) -> &str {
+        self.base64.get_or_init(|| bs64::encode(&self.serialized))
+    }
 
-    let serialized_tx = bincode::serialize(&txn).expect("Failed to serialize transaction");
-    bs58::encode(&serialized_tx).into_string()
+    pub fn base58(&self) -> &str {
+        self.base58
+            .get_or_init(|| bs58::encode(&self.serialized).into_string())
+    }
 }
 
-pub fn simulate(
-    mut ixs: Vec<Instruction>,
+/// Compiles, preflight-checks and signs the V0 transaction submitted to relayers
+pub fn build_v0<S: Signer + ?Sized>(
+    ixs: Vec<Instruction>,
     fee_payer: &Pubkey,
-    signers: &Vec<&Keypair>,
+    signers: &[&S],
     recent_blockhash: Hash,
     nonce_ix: Option<Instruction>,
-    rpc_endpoint: String,
-) {
-    if let Some(nonce_instruction) = nonce_ix {
-        ixs.insert(0, nonce_instruction);
-    }
+    alt: &[AddressLookupTableAccount],
+) -> anyhow::Result<BuiltTransaction> {
+    let versioned_message = compile_v0(ixs, fee_payer, recent_blockhash, nonce_ix, alt)?;
+    preflight_check(&versioned_message, signers, alt)?;
+    let signers = required_signers(&versioned_message, signers)?;
+    let txn = VersionedTransaction::try_new(versioned_message, &signers)?;
 
-    let txn = Transaction::new_signed_with_payer(&ixs, Some(&fee_payer), signers, recent_blockhash);
+    BuiltTransaction::new(txn)
+}
 
-    let rpc_client = RpcClient::new_with_commitment(
-        (rpc_endpoint).clone(),
-        solana_sdk::commitment_config::CommitmentConfig::processed(),
-    );
+pub fn build_v0_bs64<S: Signer + ?Sized>(
+    ixs: Vec<Instruction>,
+    fee_payer: &Pubkey,
+    signers: &[&S],
+    recent_blockhash: Hash,
+    nonce_ix: Option<Instruction>,
+    alt: &[AddressLookupTableAccount],
+) -> anyhow::Result<String> {
+    build_v0(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
+        .map(|built| built.base64().to_string())
+}
 
-    let tx_log = rpc_client.simulate_transaction(&txn);
+pub fn build_v0_bs58<S: Signer + ?Sized>(
+    ixs: Vec<Instruction>,
+    fee_payer: &Pubkey,
+    signers: &[&S],
+    recent_blockhash: Hash,
+    nonce_ix: Option<Instruction>,
+    alt: &[AddressLookupTableAccount],
+) -> anyhow::Result<String> {
+    build_v0(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
+        .map(|built| built.base58().to_string())
+}
 
-    println!("Simulate {:#?}", tx_log);
+/// Picks the signers the message requires (e.g. fee payer and a separate tip payer),
+/// ignoring extra keypairs and failing on missing ones
+pub fn required_signers<'a, S: Signer + ?Sized>(
+    message: &VersionedMessage,
+    signers: &[&'a S],
+) -> anyhow::Result<Vec<&'a S>> {
+    let num_required = message.header().num_required_signatures as usize;
+
+    message.static_account_keys()[..num_required]
+        .iter()
+        .map(|key| {
+            signers
+                .iter()
+                .find(|signer| signer.pubkey() == *key)
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("Missing signer for {}", key))
+        })
+        .collect()
 }
 
 pub trait TransactionBuilder {
-    fn build_v0_bs64(
+    fn build_v0<S: Signer + ?Sized>(
+        &self,
+        ixs: Vec<Instruction>,
+        fee_payer: &Pubkey,
+        signers: &[&S],
+        recent_blockhash: Hash,
+        nonce_ix: Option<Instruction>,
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<BuiltTransaction> {
+        build_v0(ixs, fee_payer, signers, recent_blockhash, nonce_ix, alt)
+    }
+
+    fn build_v0_bs64<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String;
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String>;
     
-    fn build_v0_bs58(
+    fn build_v0_bs58<S: Signer + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
-        alt: Vec<AddressLookupTableAccount>,
-    ) -> String;
+        alt: &[AddressLookupTableAccount],
+    ) -> anyhow::Result<String>;
 
-    fn simulate(
+    /// Simulates the V0 transaction these builders produce and returns the outcome
+    #[allow(clippy::too_many_arguments)]
+    fn simulate<S: Signer + Sync + ?Sized>(
         &self,
         ixs: Vec<Instruction>,
         fee_payer: &Pubkey,
-        signers: &Vec<&Keypair>,
+        signers: &[&S],
         recent_blockhash: Hash,
         nonce_ix: Option<Instruction>,
+        alt: &[AddressLookupTableAccount],
         rpc_endpoint: String,
-    ) {
+        config: SimulationConfig,
+    ) -> impl Future<Output = anyhow::Result<SimulationResult>> + Send {
         simulate(
             ixs,
             fee_payer,
             signers,
             recent_blockhash,
             nonce_ix,
+            alt,
             rpc_endpoint,
+            config,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn encoded_builders_return_errors_instead_of_panicking() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+        let build = |signer: &Keypair| {
+            build_v0_bs64(
+                vec![ix.clone()],
+                &payer.pubkey(),
+                &[signer],
+                Hash::default(),
+                None,
+                &[],
+            )
+        };
+
+        // Ed25519 signatures are deterministic, so both builds produce the same bytes
+        let built = build_v0(
+            vec![ix.clone()],
+            &payer.pubkey(),
+            &[&payer],
+            Hash::default(),
+            None,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(build(&payer).unwrap(), built.base64());
+        assert!(build(&other).is_err());
+    }
+
+    #[test]
+    fn compile_v0_puts_the_nonce_advance_first_once() {
+        let payer = Keypair::new();
+        let advance = solana_system_interface::instruction::advance_nonce_account(
+            &Pubkey::new_unique(),
+            &payer.pubkey(),
         );
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[1], vec![]);
+
+        let message = compile_v0(
+            vec![ix.clone(), advance.clone()],
+            &payer.pubkey(),
+            Hash::default(),
+            Some(advance.clone()),
+            &[],
+        )
+        .unwrap();
+        let VersionedMessage::V0(message) = message else {
+            panic!("compile_v0 builds V0 messages");
+        };
+        let program_ids: Vec<Pubkey> = message
+            .instructions
+            .iter()
+            .map(|compiled| message.account_keys[compiled.program_id_index as usize])
+            .collect();
+        assert_eq!(program_ids, vec![advance.program_id, ix.program_id]);
+    }
+
+    #[test]
+    fn compile_v0_rejects_other_nonce_ixs() {
+        let payer = Pubkey::new_unique();
+        let transfer =
+            solana_system_interface::instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+
+        assert!(compile_v0(vec![], &payer, Hash::default(), Some(transfer), &[]).is_err());
     }
 }
@@ -0,0 +1,338 @@
+use solana_sdk::{
+    compute_budget::{ComputeBudgetInstruction, check_id},
+    instruction::Instruction,
+};
+
+use crate::MAX_COMPUTE_UNIT_LIMIT;
+
+pub const MIN_HEAP_FRAME_BYTES: u32 = 32 * 1024;
+pub const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+pub const HEAP_FRAME_GRANULARITY: u32 = 1024;
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: u32 = 64 * 1024 * 1024;
+
+/// What tip building does with compute budget ixs already present in `pure_ix`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComputeBudgetPolicy {
+    /// Fold them into one ix per kind, `Tips` values win over `pure_ix` ones
+    #[default]
+    Merge,
+    /// Fail when a kind is set twice (by `Tips` and `pure_ix`, or twice in `pure_ix`)
+    Reject,
+}
+
+/// One value per compute budget instruction kind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComputeBudgetSettings {
+    pub compute_unit_limit: Option<u32>,
+    pub compute_unit_price: Option<u64>,
+    pub heap_frame_bytes: Option<u32>,
+    pub loaded_accounts_data_size_limit: Option<u32>,
+}
+
+impl ComputeBudgetSettings {
+    /// Folds `other` into `self`, `other` wins unless `policy` is `Reject`
+    pub fn merge(
+        &mut self,
+        other: &ComputeBudgetSettings,
+        policy: ComputeBudgetPolicy,
+    ) -> anyhow::Result<()> {
+        merge_field(
+            &mut self.compute_unit_limit,
+            other.compute_unit_limit,
+            "SetComputeUnitLimit",
+            policy,
+        )?;
+        merge_field(
+            &mut self.compute_unit_price,
+            other.compute_unit_price,
+            "SetComputeUnitPrice",
+            policy,
+        )?;
+        merge_field(
+            &mut self.heap_frame_bytes,
+            other.heap_frame_bytes,
+            "RequestHeapFrame",
+            policy,
+        )?;
+        merge_field(
+            &mut self.loaded_accounts_data_size_limit,
+            other.loaded_accounts_data_size_limit,
+            "SetLoadedAccountsDataSizeLimit",
+            policy,
+        )?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(limit) = self.compute_unit_limit
+            && limit as u64 > MAX_COMPUTE_UNIT_LIMIT
+        {
+            anyhow::bail!(
+                "Compute unit limit {} exceeds the maximum of {}",
+                limit,
+                MAX_COMPUTE_UNIT_LIMIT
+            );
+        }
+
+        if let Some(bytes) = self.heap_frame_bytes
+            && (!(MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes)
+                || bytes % HEAP_FRAME_GRANULARITY != 0)
+        {
+            anyhow::bail!(
+                "Heap frame of {} bytes must be a multiple of {} between {} and {}",
+                bytes,
+                HEAP_FRAME_GRANULARITY,
+                MIN_HEAP_FRAME_BYTES,
+                MAX_HEAP_FRAME_BYTES
+            );
+        }
+
+        if let Some(bytes) = self.loaded_accounts_data_size_limit
+            && (bytes == 0 || bytes > MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES)
+        {
+            anyhow::bail!(
+                "Loaded accounts data size limit of {} bytes must be between 1 and {}",
+                bytes,
+                MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Pulls every value into the range `validate` accepts
+    pub fn clamp(&mut self) {
+        if let Some(limit) = &mut self.compute_unit_limit {
+            *limit = (*limit).min(MAX_COMPUTE_UNIT_LIMIT as u32);
+        }
+        if let Some(bytes) = &mut self.heap_frame_bytes {
+            *bytes = (*bytes).clamp(MIN_HEAP_FRAME_BYTES, MAX_HEAP_FRAME_BYTES)
+                / HEAP_FRAME_GRANULARITY
+                * HEAP_FRAME_GRANULARITY;
+        }
+        if let Some(bytes) = &mut self.loaded_accounts_data_size_limit {
+            *bytes = (*bytes).clamp(1, MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES);
+        }
+    }
+
+    pub fn to_instructions(&self) -> Vec<Instruction> {
+        let mut ixs = Vec::new();
+
+        if let Some(units) = self.compute_unit_limit {
+            ixs.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.compute_unit_price {
+            ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
+                micro_lamports,
+            ));
+        }
+        if let Some(bytes) = self.heap_frame_bytes {
+            ixs.push(ComputeBudgetInstruction::request_heap_frame(bytes));
+        }
+        if let Some(bytes) = self.loaded_accounts_data_size_limit {
+            ixs.push(ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(bytes));
+        }
+
+        ixs
+    }
+}
+
+fn merge_field<T: Copy>(
+    current: &mut Option<T>,
+    incoming: Option<T>,
+    name: &str,
+    policy: ComputeBudgetPolicy,
+) -> anyhow::Result<()> {
+    if let Some(value) = incoming {
+        if current.is_some() && policy == ComputeBudgetPolicy::Reject {
+            anyhow::bail!("Duplicate {} compute budget instruction", name);
+        }
+        *current = Some(value);
+    }
+    Ok(())
+}
+
+pub fn is_compute_budget_ix(ix: &Instruction) -> bool {
+    check_id(&ix.program_id)
+}
+
+pub fn parse_compute_budget_ix(ix: &Instruction) -> anyhow::Result<ComputeBudgetInstruction> {
+    let (tag, rest) = ix
+        .data
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Empty compute budget instruction"))?;
+
+    let u32_arg = || -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(rest.try_into().map_err(|_| {
+            anyhow::anyhow!("Malformed compute budget instruction data")
+        })?))
+    };
+
+    match tag {
+        1 => Ok(ComputeBudgetInstruction::RequestHeapFrame(u32_arg()?)),
+        2 => Ok(ComputeBudgetInstruction::SetComputeUnitLimit(u32_arg()?)),
+        3 => Ok(ComputeBudgetInstruction::SetComputeUnitPrice(
+            u64::from_le_bytes(
+                rest.try_into()
+                    .map_err(|_| anyhow::anyhow!("Malformed compute budget instruction data"))?,
+            ),
+        )),
+        4 => Ok(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(
+            u32_arg()?,
+        )),
+        _ => anyhow::bail!("Unsupported compute budget instruction tag {}", tag),
+    }
+}
+
+/// Splits `ixs` into the compute budget they request and the remaining instructions
+pub fn extract_compute_budget(
+    ixs: &[Instruction],
+    policy: ComputeBudgetPolicy,
+) -> anyhow::Result<(ComputeBudgetSettings, Vec<Instruction>)> {
+    let mut settings = ComputeBudgetSettings::default();
+    let mut others = Vec::with_capacity(ixs.len());
+
+    for ix in ixs {
+        if !is_compute_budget_ix(ix) {
+            others.push(ix.clone());
+            continue;
+        }
+
+        let mut found = ComputeBudgetSettings::default();
+        match parse_compute_budget_ix(ix)? {
+            ComputeBudgetInstruction::RequestHeapFrame(bytes) => {
+                found.heap_frame_bytes = Some(bytes)
+            }
+            ComputeBudgetInstruction::SetComputeUnitLimit(units) => {
+                found.compute_unit_limit = Some(units)
+            }
+            ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports) => {
+                found.compute_unit_price = Some(micro_lamports)
+            }
+            ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes) => {
+                found.loaded_accounts_data_size_limit = Some(bytes)
+            }
+            ComputeBudgetInstruction::Unused => {
+                anyhow::bail!("Deprecated compute budget instruction")
+            }
+        }
+        settings.merge(&found, policy)?;
+    }
+
+    Ok((settings, others))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn other_ix() -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[7], vec![])
+    }
+
+    #[test]
+    fn extracts_compute_budget_and_keeps_other_ixs() {
+        let other = other_ix();
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            other.clone(),
+            ComputeBudgetInstruction::set_compute_unit_price(42),
+            ComputeBudgetInstruction::request_heap_frame(64 * 1024),
+        ];
+
+        let (settings, others) = extract_compute_budget(&ixs, ComputeBudgetPolicy::Reject).unwrap();
+        assert_eq!(
+            settings,
+            ComputeBudgetSettings {
+                compute_unit_limit: Some(300_000),
+                compute_unit_price: Some(42),
+                heap_frame_bytes: Some(64 * 1024),
+                loaded_accounts_data_size_limit: None,
+            }
+        );
+        assert_eq!(others, vec![other]);
+        assert_eq!(settings.to_instructions().len(), 3);
+    }
+
+    #[test]
+    fn merge_keeps_the_last_value() {
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_price(1),
+            ComputeBudgetInstruction::set_compute_unit_price(2),
+        ];
+        let (mut settings, _) = extract_compute_budget(&ixs, ComputeBudgetPolicy::Merge).unwrap();
+        assert_eq!(settings.compute_unit_price, Some(2));
+
+        settings
+            .merge(
+                &ComputeBudgetSettings {
+                    compute_unit_price: Some(3),
+                    ..Default::default()
+                },
+                ComputeBudgetPolicy::Merge,
+            )
+            .unwrap();
+        assert_eq!(settings.compute_unit_price, Some(3));
+    }
+
+    #[test]
+    fn reject_fails_on_duplicates() {
+        let ixs = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(1),
+            ComputeBudgetInstruction::set_compute_unit_limit(2),
+        ];
+        assert!(extract_compute_budget(&ixs, ComputeBudgetPolicy::Reject).is_err());
+
+        let (mut settings, _) =
+            extract_compute_budget(&ixs[..1], ComputeBudgetPolicy::Reject).unwrap();
+        let from_tips = ComputeBudgetSettings {
+            compute_unit_limit: Some(3),
+            ..Default::default()
+        };
+        assert!(
+            settings
+                .merge(&from_tips, ComputeBudgetPolicy::Reject)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn malformed_ixs_are_rejected() {
+        let mut truncated = ComputeBudgetInstruction::set_compute_unit_price(1);
+        truncated.data.pop();
+        assert!(extract_compute_budget(&[truncated], ComputeBudgetPolicy::Merge).is_err());
+
+        let mut unknown = ComputeBudgetInstruction::set_compute_unit_limit(1);
+        unknown.data[0] = 9;
+        assert!(extract_compute_budget(&[unknown], ComputeBudgetPolicy::Merge).is_err());
+    }
+
+    #[test]
+    fn validate_enforces_runtime_limits() {
+        let valid = ComputeBudgetSettings {
+            compute_unit_limit: Some(MAX_COMPUTE_UNIT_LIMIT as u32),
+            heap_frame_bytes: Some(MAX_HEAP_FRAME_BYTES),
+            loaded_accounts_data_size_limit: Some(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+            ..Default::default()
+        };
+        assert!(valid.validate().is_ok());
+
+        for invalid in [
+            ComputeBudgetSettings {
+                compute_unit_limit: Some(MAX_COMPUTE_UNIT_LIMIT as u32 + 1),
+                ..Default::default()
+            },
+            ComputeBudgetSettings {
+                heap_frame_bytes: Some(MIN_HEAP_FRAME_BYTES + 1),
+                ..Default::default()
+            },
+            ComputeBudgetSettings {
+                loaded_accounts_data_size_limit: Some(0),
+                ..Default::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction, hash::Hash, instruction::Instruction,
+    message::AddressLookupTableAccount, pubkey::Pubkey, signer::Signer,
+};
+
+use crate::*;
+
+pub const DEFAULT_CU_MARGIN: f64 = 0.1;
+
+/// Simulates `ixs` as the same V0 message (ALTs included) with the maximum CU limit and
+/// returns the consumed units plus `margin` (0.1 = 10%), capped at `MAX_COMPUTE_UNIT_LIMIT`
+pub async fn estimate_compute_unit_limit(
+    rpc_client: &RpcClient,
+    ixs: &[Instruction],
+    fee_payer: &Pubkey,
+    nonce_ix: Option<Instruction>,
+    alt: &[AddressLookupTableAccount],
+    margin: f64,
+) -> anyhow::Result<u32> {
+    // Lift any CU limit so the simulation itself can't run out of compute
+    let (mut settings, others) = extract_compute_budget(ixs, ComputeBudgetPolicy::Merge)?;
+    settings.compute_unit_limit = Some(MAX_COMPUTE_UNIT_LIMIT as u32);
+
+    let mut sim_ixs = settings.to_instructions();
+    sim_ixs.extend(others);
+
+    // The blockhash is replaced by the RPC and signatures are not verified
+    let result = simulate_with_client(
+        rpc_client,
+        sim_ixs,
+        fee_payer,
+        &[] as &[&dyn Signer],
+        Hash::default(),
+        nonce_ix,
+        alt,
+        SimulationConfig::default(),
+    )
+    .await?;
+
+    if let Some(err) = result.err {
+        anyhow::bail!("Simulation failed: {} (logs: {:?})", err, result.logs);
+    }
+
+    let units_consumed = result
+        .units_consumed
+        .ok_or_else(|| anyhow::anyhow!("Simulation did not report units consumed"))?;
+
+    let with_margin = (units_consumed as f64 * (1.0 + margin.max(0.0))).ceil() as u64;
+    Ok(with_margin.min(MAX_COMPUTE_UNIT_LIMIT) as u32)
+}
+
+/// Returns `tip_config` with `cu` set from a simulation of the tip-bearing transaction
+pub async fn with_estimated_cu(
+    rpc_client: &RpcClient,
+    tip_config: &Tips,
+    tip_ix: Instruction,
+    nonce_ix: Option<Instruction>,
+    alt: &[AddressLookupTableAccount],
+    margin: f64,
+) -> anyhow::Result<Tips> {
+    let ixs = try_build_tip_ixs(tip_config, tip_ix)?;
+    let cu =
+        estimate_compute_unit_limit(rpc_client, &ixs, &tip_config.payer, nonce_ix, alt, margin)
+            .await?;
+
+    let mut tips = tip_config.clone();
+    tips.cu = Some(cu as u64);
+    // Drop any limit from `pure_ix` so `ComputeBudgetPolicy::Reject` doesn't see a duplicate
+    tips.pure_ix.retain(|ix| {
+        !(is_compute_budget_ix(ix)
+            && matches!(
+                parse_compute_budget_ix(ix),
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(_))
+            ))
+    });
+    Ok(tips)
+}
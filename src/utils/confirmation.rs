@@ -0,0 +1,479 @@
+use futures::StreamExt;
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::RpcSignatureSubscribeConfig,
+    rpc_response::{ProcessedSignatureResult, RpcSignatureResult},
+};
+use solana_sdk::{
+    clock::Slot,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    signature::Signature,
+    transaction::TransactionError,
+};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{oneshot, watch},
+    time::sleep,
+};
+
+pub const CONFIRMATION_POLL_INTERVAL_MS: u64 = 400;
+pub const CONFIRMATION_TIMEOUT_MS: u64 = 30_000;
+
+/// `getSignatureStatuses` accepts at most this many signatures per call
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// Where a tracked signature stands on chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmationStatus {
+    /// Not seen yet
+    Pending,
+    Processed {
+        slot: Slot,
+    },
+    Confirmed {
+        slot: Slot,
+    },
+    Finalized {
+        slot: Slot,
+    },
+    /// Landed but the transaction failed, fees were still charged
+    Failed {
+        slot: Slot,
+        err: TransactionError,
+    },
+    /// Never seen before the blockhash expired or the timeout passed
+    Expired,
+}
+
+impl ConfirmationStatus {
+    fn for_level(level: CommitmentLevel, slot: Slot) -> Self {
+        match level {
+            CommitmentLevel::Finalized => Self::Finalized { slot },
+            CommitmentLevel::Confirmed => Self::Confirmed { slot },
+            CommitmentLevel::Processed => Self::Processed { slot },
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::Pending => 0,
+            Self::Processed { .. } => 1,
+            Self::Confirmed { .. } => 2,
+            Self::Finalized { .. } => 3,
+            Self::Failed { .. } | Self::Expired => 4,
+        }
+    }
+
+    pub fn slot(&self) -> Option<Slot> {
+        match self {
+            Self::Processed { slot }
+            | Self::Confirmed { slot }
+            | Self::Finalized { slot }
+            | Self::Failed { slot, .. } => Some(*slot),
+            Self::Pending | Self::Expired => None,
+        }
+    }
+
+    /// Landed without error at `commitment` or stronger
+    pub fn satisfies(&self, commitment: CommitmentConfig) -> bool {
+        let target = Self::for_level(commitment.commitment, 0).rank();
+        !matches!(self, Self::Failed { .. } | Self::Expired) && self.rank() >= target
+    }
+
+    /// No further update matters to a waiter targeting `commitment`
+    pub fn is_final_for(&self, commitment: CommitmentConfig) -> bool {
+        matches!(self, Self::Failed { .. } | Self::Expired) || self.satisfies(commitment)
+    }
+}
+
+struct TrackedSignature {
+    commitment: CommitmentConfig,
+    last_valid_block_height: Option<u64>,
+    deadline: Instant,
+    status_tx: watch::Sender<ConfirmationStatus>,
+}
+
+/// Resolves submitted signatures to their on-chain status. Every tracked signature is
+/// polled together with `getSignatureStatuses`; with a websocket URL each waiter also
+/// races a `signatureSubscribe` notification, over one connection shared by the tracker.
+pub struct ConfirmationTracker {
+    pub rpc_client: RpcClient,
+    pub ws_url: Option<String>,
+    pub poll_interval: Duration,
+    /// Expiry for signatures tracked without a `last_valid_block_height`
+    pub timeout: Duration,
+    tracked: Mutex<HashMap<Signature, TrackedSignature>>,
+    poller: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Opened by the first subscription, reopened after a subscription fails on it
+    pubsub: tokio::sync::Mutex<Option<Arc<PubsubClient>>>,
+}
+
+impl fmt::Debug for ConfirmationTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfirmationTracker")
+            .field("rpc_url", &self.rpc_client.url())
+            .field("ws_url", &self.ws_url)
+            .field("poll_interval", &self.poll_interval)
+            .field("timeout", &self.timeout)
+            .field("tracked", &self.tracked.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc_endpoint: String) -> Self {
+        Self {
+            rpc_client: RpcClient::new_with_commitment(rpc_endpoint, CommitmentConfig::confirmed()),
+            ws_url: None,
+            poll_interval: Duration::from_millis(CONFIRMATION_POLL_INTERVAL_MS),
+            timeout: Duration::from_millis(CONFIRMATION_TIMEOUT_MS),
+            tracked: Mutex::new(HashMap::new()),
+            poller: Mutex::new(None),
+            pubsub: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// Also listen with `signatureSubscribe`, e.g. `wss://api.mainnet-beta.solana.com`
+    pub fn with_websocket(mut self, ws_url: String) -> Self {
+        self.ws_url = Some(ws_url);
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn tracked_count(&self) -> usize {
+        self.tracked.lock().unwrap().len()
+    }
+
+    /// Starts tracking `signature` until it reaches `commitment`, fails or expires.
+    /// Expiry uses `last_valid_block_height` when known, `timeout` otherwise.
+    pub fn track(
+        self: &Arc<Self>,
+        signature: Signature,
+        commitment: CommitmentConfig,
+        last_valid_block_height: Option<u64>,
+    ) -> watch::Receiver<ConfirmationStatus> {
+        let receiver = match self.tracked.lock().unwrap().entry(signature) {
+            Entry::Occupied(mut entry) => {
+                let tracked = entry.get_mut();
+                // Keep polling until the strictest waiter is satisfied
+                if ConfirmationStatus::for_level(commitment.commitment, 0).rank()
+                    > ConfirmationStatus::for_level(tracked.commitment.commitment, 0).rank()
+                {
+                    tracked.commitment = commitment;
+                }
+                tracked.status_tx.subscribe()
+            }
+            Entry::Vacant(entry) => {
+                let (status_tx, status_rx) = watch::channel(ConfirmationStatus::Pending);
+                entry.insert(TrackedSignature {
+                    commitment,
+                    last_valid_block_height,
+                    deadline: Instant::now() + self.timeout,
+                    status_tx,
+                });
+                status_rx
+            }
+        };
+
+        self.ensure_polling();
+        receiver
+    }
+
+    /// Tracks `signature` and waits for its final status
+    pub async fn wait(
+        self: &Arc<Self>,
+        signature: Signature,
+        commitment: CommitmentConfig,
+        last_valid_block_height: Option<u64>,
+    ) -> ConfirmationStatus {
+        let mut status_rx = self.track(signature, commitment, last_valid_block_height);
+        let polled = async {
+            match status_rx
+                .wait_for(|status| status.is_final_for(commitment))
+                .await
+            {
+                Ok(status) => status.clone(),
+                Err(_) => ConfirmationStatus::Expired,
+            }
+        };
+
+        match &self.ws_url {
+            // A failed subscription disables its branch and leaves the poll to answer
+            Some(ws_url) => tokio::select! {
+                status = polled => status,
+                Some(status) = self.subscribe_signature(ws_url, &signature, commitment) => status,
+            },
+            None => polled.await,
+        }
+    }
+
+    /// The tracker's websocket connection, opened on first use
+    async fn pubsub(&self, ws_url: &str) -> Option<Arc<PubsubClient>> {
+        let mut pubsub = self.pubsub.lock().await;
+        if let Some(client) = pubsub.as_ref() {
+            return Some(Arc::clone(client));
+        }
+
+        match PubsubClient::new(ws_url).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                *pubsub = Some(Arc::clone(&client));
+                Some(client)
+            }
+            Err(err) => {
+                eprintln!("Websocket connection to {} failed: {}", ws_url, err);
+                None
+            }
+        }
+    }
+
+    /// First `signatureSubscribe` notification for `signature`, `None` if the subscription fails.
+    /// The subscription lives in its own task, which unsubscribes even when this future is
+    /// dropped, e.g. after the poll answered first.
+    async fn subscribe_signature(
+        &self,
+        ws_url: &str,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Option<ConfirmationStatus> {
+        let client = self.pubsub(ws_url).await?;
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(commitment),
+            enable_received_notification: Some(false),
+        };
+        // Dropped along with this future, which ends the subscription task's wait
+        let (_waiting_tx, waiting_rx) = oneshot::channel::<()>();
+        let (subscribed_tx, subscribed_rx) = oneshot::channel();
+        let subscriber = Arc::clone(&client);
+        let signature = *signature;
+        tokio::spawn(async move {
+            let (mut notifications, unsubscribe) = match subscriber
+                .signature_subscribe(&signature, Some(config))
+                .await
+            {
+                Ok(subscription) => subscription,
+                Err(err) => {
+                    let _ = subscribed_tx.send(Err(err));
+                    return;
+                }
+            };
+            let notification = tokio::select! {
+                notification = notifications.next() => notification,
+                _ = waiting_rx => None,
+            };
+            unsubscribe().await;
+            let _ = subscribed_tx.send(Ok(notification));
+        });
+
+        let notification = match subscribed_rx.await.ok()? {
+            Ok(notification) => notification,
+            Err(err) => {
+                eprintln!("Signature subscription for {} failed: {}", signature, err);
+                // The connection is likely gone, the next waiter opens a new one
+                let mut pubsub = self.pubsub.lock().await;
+                if pubsub
+                    .as_ref()
+                    .is_some_and(|current| Arc::ptr_eq(current, &client))
+                {
+                    *pubsub = None;
+                }
+                return None;
+            }
+        };
+
+        let response = notification?;
+        let slot = response.context.slot;
+        match response.value {
+            RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: Some(err) }) => {
+                Some(ConfirmationStatus::Failed { slot, err })
+            }
+            RpcSignatureResult::ProcessedSignature(ProcessedSignatureResult { err: None }) => {
+                Some(ConfirmationStatus::for_level(commitment.commitment, slot))
+            }
+            RpcSignatureResult::ReceivedSignature(_) => None,
+        }
+    }
+
+    fn ensure_polling(self: &Arc<Self>) {
+        let mut poller = self.poller.lock().unwrap();
+        if poller.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            return;
+        }
+
+        // The poller only holds a weak reference so dropping the tracker stops it
+        let tracker = Arc::downgrade(self);
+        *poller = Some(tokio::spawn(poll_loop(tracker)));
+    }
+
+    /// One round of `getSignatureStatuses` over every tracked signature
+    pub async fn poll(&self) {
+        let (signatures, needs_block_height) = {
+            let tracked = self.tracked.lock().unwrap();
+            let signatures: Vec<Signature> = tracked.keys().copied().collect();
+            let needs_block_height = tracked
+                .values()
+                .any(|tracked| tracked.last_valid_block_height.is_some());
+            (signatures, needs_block_height)
+        };
+        if signatures.is_empty() {
+            return;
+        }
+
+        let block_height = if needs_block_height {
+            self.rpc_client.get_block_height().await.ok()
+        } else {
+            None
+        };
+
+        for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+            let statuses = match self.rpc_client.get_signature_statuses(chunk).await {
+                Ok(response) => response.value,
+                Err(err) => {
+                    eprintln!("Signature status poll failed: {}", err);
+                    continue;
+                }
+            };
+
+            let mut tracked = self.tracked.lock().unwrap();
+            for (signature, status) in chunk.iter().zip(statuses) {
+                let Some(entry) = tracked.get(signature) else {
+                    continue;
+                };
+
+                let next = match status {
+                    Some(status) => match status.err.clone() {
+                        Some(err) => ConfirmationStatus::Failed {
+                            slot: status.slot,
+                            err,
+                        },
+                        None if status.satisfies_commitment(CommitmentConfig::finalized()) => {
+                            ConfirmationStatus::Finalized { slot: status.slot }
+                        }
+                        None if status.satisfies_commitment(CommitmentConfig::confirmed()) => {
+                            ConfirmationStatus::Confirmed { slot: status.slot }
+                        }
+                        None => ConfirmationStatus::Processed { slot: status.slot },
+                    },
+                    None => {
+                        let expired = match (entry.last_valid_block_height, block_height) {
+                            (Some(last_valid), Some(height)) => height > last_valid,
+                            _ => Instant::now() >= entry.deadline,
+                        };
+                        if expired {
+                            ConfirmationStatus::Expired
+                        } else {
+                            ConfirmationStatus::Pending
+                        }
+                    }
+                };
+
+                let finished = next.is_final_for(entry.commitment);
+                entry.status_tx.send_if_modified(|current| {
+                    let changed = *current != next;
+                    *current = next;
+                    changed
+                });
+                if finished {
+                    tracked.remove(signature);
+                }
+            }
+        }
+    }
+}
+
+async fn poll_loop(tracker: Weak<ConfirmationTracker>) {
+    loop {
+        let Some(tracker) = tracker.upgrade() else {
+            return;
+        };
+        tracker.poll().await;
+        let poll_interval = tracker.poll_interval;
+        drop(tracker);
+        sleep(poll_interval).await;
+    }
+}
+
+/// Awaits a client's `send_transaction`, then, with a tracker, the transaction's confirmation.
+/// `signature` is the one of the transaction sent, e.g. `BuiltTransaction::signature`.
+pub async fn send_and_confirm<T, F>(
+    send: F,
+    signature: Signature,
+    tracker: Option<&Arc<ConfirmationTracker>>,
+    commitment: CommitmentConfig,
+    last_valid_block_height: Option<u64>,
+) -> anyhow::Result<(T, Option<ConfirmationStatus>)>
+where
+    F: Future<Output = anyhow::Result<T>>,
+{
+    let response = send.await?;
+    let status = match tracker {
+        Some(tracker) => Some(
+            tracker
+                .wait(signature, commitment, last_valid_block_height)
+                .await,
+        ),
+        None => None,
+    };
+    Ok((response, status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stronger_commitments_satisfy_weaker_ones() {
+        let confirmed = ConfirmationStatus::Confirmed { slot: 7 };
+
+        assert!(confirmed.satisfies(CommitmentConfig::processed()));
+        assert!(confirmed.satisfies(CommitmentConfig::confirmed()));
+        assert!(!confirmed.satisfies(CommitmentConfig::finalized()));
+        assert!(!confirmed.is_final_for(CommitmentConfig::finalized()));
+        assert_eq!(confirmed.slot(), Some(7));
+    }
+
+    #[test]
+    fn failures_and_expiry_are_final_but_never_satisfy() {
+        let failed = ConfirmationStatus::Failed {
+            slot: 7,
+            err: TransactionError::AccountInUse,
+        };
+
+        for status in [failed, ConfirmationStatus::Expired] {
+            assert!(!status.satisfies(CommitmentConfig::processed()));
+            assert!(status.is_final_for(CommitmentConfig::finalized()));
+        }
+        assert!(!ConfirmationStatus::Pending.is_final_for(CommitmentConfig::processed()));
+    }
+
+    #[tokio::test]
+    async fn unreachable_websocket_yields_no_notification() {
+        let tracker = ConfirmationTracker::new("http://127.0.0.1:1".to_string())
+            .with_websocket("ws://127.0.0.1:1".to_string());
+
+        let subscribed = tracker
+            .subscribe_signature(
+                "ws://127.0.0.1:1",
+                &Signature::default(),
+                CommitmentConfig::confirmed(),
+            )
+            .await;
+        assert_eq!(subscribed, None);
+        assert!(tracker.pubsub.lock().await.is_none());
+    }
+}
@@ -1,12 +1,36 @@
+pub mod alt_cache;
+pub mod budget;
+pub mod blockhash;
 pub mod build;
+pub mod compute_budget;
+pub mod compute_units;
+pub mod confirmation;
+pub mod nonce;
 pub mod ping;
+pub mod preflight;
+pub mod priority_fee;
+pub mod rate_limit;
+pub mod remote_signer;
 pub mod return_type;
 pub mod services;
+pub mod simulate;
 pub mod tip;
 pub mod time_elapsed;
+pub use alt_cache::*;
+pub use budget::*;
+pub use blockhash::*;
 pub use build::*;
+pub use compute_budget::*;
+pub use compute_units::*;
+pub use confirmation::*;
+pub use nonce::*;
 pub use ping::*;
+pub use preflight::*;
+pub use priority_fee::*;
+pub use rate_limit::*;
+pub use remote_signer::*;
 pub use return_type::*;
 pub use services::*;
+pub use simulate::*;
 pub use tip::*;
 pub use time_elapsed::*;
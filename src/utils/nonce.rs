@@ -0,0 +1,205 @@
+use solana_client::nonblocking::{
+    nonce_utils::{data_from_account, get_account_with_commitment},
+    rpc_client::RpcClient,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction, pubkey::Pubkey,
+};
+use solana_system_interface::{instruction::advance_nonce_account, program as system_program};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::time::sleep;
+
+pub const NONCE_POLL_INTERVAL_MS: u64 = 400;
+
+/// Bincode tag of `SystemInstruction::AdvanceNonceAccount`
+const ADVANCE_NONCE_ACCOUNT_TAG: [u8; 4] = [4, 0, 0, 0];
+
+/// Durable nonce account state, the `blockhash` is what transactions must be built with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceInfo {
+    pub address: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+    pub lamports_per_signature: u64,
+}
+
+impl NonceInfo {
+    /// `advance_nonce_account` instruction, must be the first instruction of the transaction
+    pub fn advance_ix(&self) -> Instruction {
+        advance_nonce_account(&self.address, &self.authority)
+    }
+}
+
+pub fn is_advance_nonce_ix(ix: &Instruction) -> bool {
+    ix.program_id == system_program::id() && ix.data.starts_with(&ADVANCE_NONCE_ACCOUNT_TAG)
+}
+
+pub async fn fetch_nonce(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+    commitment: CommitmentConfig,
+) -> anyhow::Result<NonceInfo> {
+    let account = get_account_with_commitment(rpc_client, address, commitment).await?;
+    let data = data_from_account(&account)?;
+
+    Ok(NonceInfo {
+        address: *address,
+        authority: data.authority,
+        blockhash: data.blockhash(),
+        lamports_per_signature: data.get_lamports_per_signature(),
+    })
+}
+
+/// Whether the nonce moved past `info.blockhash`, i.e. a transaction built on it landed
+pub async fn is_nonce_consumed(
+    rpc_client: &RpcClient,
+    info: &NonceInfo,
+    commitment: CommitmentConfig,
+) -> anyhow::Result<bool> {
+    let current = fetch_nonce(rpc_client, &info.address, commitment).await?;
+    Ok(current.blockhash != info.blockhash)
+}
+
+/// Polls until the nonce is consumed, returning its new state, or fails after `timeout`
+pub async fn wait_for_nonce_consumed(
+    rpc_client: &RpcClient,
+    info: &NonceInfo,
+    commitment: CommitmentConfig,
+    timeout: Duration,
+) -> anyhow::Result<NonceInfo> {
+    let start = Instant::now();
+
+    loop {
+        let current = fetch_nonce(rpc_client, &info.address, commitment).await?;
+        if current.blockhash != info.blockhash {
+            return Ok(current);
+        }
+
+        if start.elapsed() >= timeout {
+            anyhow::bail!(
+                "Nonce {} was not consumed within {:?}",
+                info.address,
+                timeout
+            );
+        }
+        sleep(Duration::from_millis(NONCE_POLL_INTERVAL_MS)).await;
+    }
+}
+
+/// Nonce accounts handed out one at a time so concurrent strategies never share a nonce
+pub struct NoncePool {
+    pub rpc_client: RpcClient,
+    pub accounts: Vec<Pubkey>,
+    leased: Mutex<HashSet<Pubkey>>,
+}
+
+impl NoncePool {
+    pub fn new(rpc_endpoint: String, accounts: Vec<Pubkey>) -> Self {
+        Self {
+            rpc_client: RpcClient::new_with_commitment(rpc_endpoint, CommitmentConfig::confirmed()),
+            accounts,
+            leased: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn available(&self) -> usize {
+        self.accounts.len() - self.leased.lock().unwrap().len()
+    }
+
+    /// Leases a free nonce account with freshly fetched state, released when the lease drops
+    pub async fn lease(self: &Arc<Self>) -> anyhow::Result<NonceLease> {
+        let address = {
+            let mut leased = self.leased.lock().unwrap();
+            let address = self
+                .accounts
+                .iter()
+                .find(|address| !leased.contains(address))
+                .copied()
+                .ok_or_else(|| {
+                    anyhow::anyhow!("All {} nonce accounts are leased", self.accounts.len())
+                })?;
+            leased.insert(address);
+            address
+        };
+
+        // Build the lease first so a failed fetch releases the account
+        let mut lease = NonceLease {
+            pool: Arc::clone(self),
+            info: NonceInfo {
+                address,
+                authority: Pubkey::default(),
+                blockhash: Hash::default(),
+                lamports_per_signature: 0,
+            },
+        };
+        lease.info = fetch_nonce(&self.rpc_client, &address, self.rpc_client.commitment()).await?;
+        Ok(lease)
+    }
+}
+
+pub struct NonceLease {
+    pool: Arc<NoncePool>,
+    pub info: NonceInfo,
+}
+
+impl NonceLease {
+    pub fn advance_ix(&self) -> Instruction {
+        self.info.advance_ix()
+    }
+
+    pub async fn is_consumed(&self) -> anyhow::Result<bool> {
+        is_nonce_consumed(
+            &self.pool.rpc_client,
+            &self.info,
+            self.pool.rpc_client.commitment(),
+        )
+        .await
+    }
+
+    pub async fn wait_consumed(&self, timeout: Duration) -> anyhow::Result<NonceInfo> {
+        wait_for_nonce_consumed(
+            &self.pool.rpc_client,
+            &self.info,
+            self.pool.rpc_client.commitment(),
+            timeout,
+        )
+        .await
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        self.pool.leased.lock().unwrap().remove(&self.info.address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_system_interface::instruction::{authorize_nonce_account, transfer};
+
+    #[test]
+    fn only_advance_nonce_ixs_are_recognised() {
+        let nonce = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+
+        assert!(is_advance_nonce_ix(&advance_nonce_account(
+            &nonce, &authority
+        )));
+        assert!(!is_advance_nonce_ix(&transfer(&authority, &nonce, 1)));
+        assert!(!is_advance_nonce_ix(&authorize_nonce_account(
+            &nonce,
+            &authority,
+            &Pubkey::new_unique()
+        )));
+
+        // Same data under another program
+        let mut foreign = advance_nonce_account(&nonce, &authority);
+        foreign.program_id = Pubkey::new_unique();
+        assert!(!is_advance_nonce_ix(&foreign));
+    }
+}
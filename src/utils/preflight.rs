@@ -0,0 +1,299 @@
+use solana_sdk::{
+    message::{AddressLookupTableAccount, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    transaction::VersionedTransaction,
+};
+use std::{collections::HashSet, fmt};
+
+/// Account locks a transaction may take, static and lookup-table loaded combined.
+/// Writable and readonly locks count alike, there is no separate writable limit.
+pub const MAX_TX_ACCOUNT_LOCKS: usize = 128;
+/// Account indexes are a `u8`, so a message can't reference more keys than this
+pub const MAX_ACCOUNT_KEYS: usize = 256;
+
+/// Addresses a single lookup table can hold
+pub const MAX_LOOKUP_TABLE_ADDRESSES: usize = 256;
+
+/// Bytes an account key costs as a static key (32) versus as a lookup-table index (1)
+const ALT_SAVING_PER_KEY: usize = 31;
+/// Table address plus the two index vector lengths
+const ALT_OVERHEAD_BYTES: usize = 34;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightError {
+    MissingSigners {
+        missing: Vec<Pubkey>,
+    },
+    TooLarge {
+        size_bytes: usize,
+        limit: usize,
+        suggestion: String,
+    },
+    TooManyAccountKeys {
+        num_keys: usize,
+        limit: usize,
+    },
+    TooManyAccountLocks {
+        num_locks: usize,
+        limit: usize,
+    },
+}
+
+impl fmt::Display for PreflightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreflightError::MissingSigners { missing } => {
+                let keys: Vec<String> = missing.iter().map(|key| key.to_string()).collect();
+                write!(f, "missing signers for {}", keys.join(", "))
+            }
+            PreflightError::TooLarge {
+                size_bytes,
+                limit,
+                suggestion,
+            } => write!(
+                f,
+                "transaction is {} bytes, over the {} byte packet limit; {}",
+                size_bytes, limit, suggestion
+            ),
+            PreflightError::TooManyAccountKeys { num_keys, limit } => write!(
+                f,
+                "message references {} account keys, over the limit of {}",
+                num_keys, limit
+            ),
+            PreflightError::TooManyAccountLocks { num_locks, limit } => write!(
+                f,
+                "transaction locks {} accounts, over the limit of {}",
+                num_locks, limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PreflightError {}
+
+/// Validates signer coverage, account key and lock counts and packet size before signing
+pub fn preflight_check<S: Signer + ?Sized>(
+    message: &VersionedMessage,
+    signers: &[&S],
+    alt: &[AddressLookupTableAccount],
+) -> Result<(), PreflightError> {
+    let static_keys = message.static_account_keys();
+    let num_required = message.header().num_required_signatures as usize;
+
+    let missing: Vec<Pubkey> = static_keys[..num_required]
+        .iter()
+        .filter(|key| !signers.iter().any(|signer| signer.pubkey() == **key))
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(PreflightError::MissingSigners { missing });
+    }
+
+    let (loaded_writable, loaded_readonly) = match message.address_table_lookups() {
+        Some(lookups) => lookups.iter().fold((0, 0), |(w, r), lookup| {
+            (
+                w + lookup.writable_indexes.len(),
+                r + lookup.readonly_indexes.len(),
+            )
+        }),
+        None => (0, 0),
+    };
+
+    let num_keys = static_keys.len() + loaded_writable + loaded_readonly;
+    if num_keys > MAX_ACCOUNT_KEYS {
+        return Err(PreflightError::TooManyAccountKeys {
+            num_keys,
+            limit: MAX_ACCOUNT_KEYS,
+        });
+    }
+
+    if num_keys > MAX_TX_ACCOUNT_LOCKS {
+        return Err(PreflightError::TooManyAccountLocks {
+            num_locks: num_keys,
+            limit: MAX_TX_ACCOUNT_LOCKS,
+        });
+    }
+
+    // Signatures have a fixed size, so an unsigned copy serializes to the final length
+    let unsigned = VersionedTransaction {
+        signatures: vec![Signature::default(); num_required],
+        message: message.clone(),
+    };
+    let size_bytes = bincode::serialized_size(&unsigned).unwrap_or(u64::MAX) as usize;
+    if size_bytes > PACKET_DATA_SIZE {
+        return Err(PreflightError::TooLarge {
+            size_bytes,
+            limit: PACKET_DATA_SIZE,
+            suggestion: shrink_suggestion(message, alt, size_bytes - PACKET_DATA_SIZE),
+        });
+    }
+
+    Ok(())
+}
+
+/// Explains how many static keys must move into a lookup table (given or new) to fit
+fn shrink_suggestion(
+    message: &VersionedMessage,
+    alt: &[AddressLookupTableAccount],
+    excess_bytes: usize,
+) -> String {
+    let static_keys = message.static_account_keys();
+    let num_signers = message.header().num_required_signatures as usize;
+
+    // Signers and invoked programs must stay static
+    let programs: HashSet<&Pubkey> = message
+        .instructions()
+        .iter()
+        .filter_map(|ix| static_keys.get(ix.program_id_index as usize))
+        .collect();
+    let movable: Vec<&Pubkey> = static_keys[num_signers..]
+        .iter()
+        .filter(|key| !programs.contains(key))
+        .collect();
+
+    if movable.is_empty() {
+        return "no static account can move into a lookup table, split the instructions \
+                across transactions"
+            .to_string();
+    }
+
+    let needed = (excess_bytes + ALT_OVERHEAD_BYTES).div_ceil(ALT_SAVING_PER_KEY);
+    if needed > movable.len() {
+        return format!(
+            "moving all {} movable static accounts into a lookup table saves at most {} bytes, \
+             split the instructions across transactions",
+            movable.len(),
+            (movable.len() * ALT_SAVING_PER_KEY).saturating_sub(ALT_OVERHEAD_BYTES)
+        );
+    }
+
+    let keys: Vec<String> = movable.iter().map(|key| key.to_string()).collect();
+
+    // Extending a given table avoids the per-table overhead
+    let roomiest = alt
+        .iter()
+        .filter(|table| MAX_LOOKUP_TABLE_ADDRESSES.saturating_sub(table.addresses.len()) >= needed)
+        .min_by_key(|table| table.addresses.len());
+    match roomiest {
+        Some(table) => format!(
+            "extend lookup table {} with at least {} of these static accounts: {}",
+            table.key,
+            needed,
+            keys.join(", ")
+        ),
+        None => format!(
+            "move at least {} of these static accounts into a new lookup table: {}",
+            needed,
+            keys.join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::v0,
+        signature::Keypair,
+    };
+
+    fn message(payer: &Keypair, accounts: usize, data_len: usize) -> VersionedMessage {
+        let metas = (0..accounts)
+            .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+            .collect();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &vec![0; data_len], metas);
+        VersionedMessage::V0(
+            v0::Message::try_compile(&payer.pubkey(), &[ix], &[], Hash::default()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn small_transaction_passes() {
+        let payer = Keypair::new();
+        assert_eq!(
+            preflight_check(&message(&payer, 3, 16), &[&payer], &[]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn missing_signer_is_reported() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+
+        assert_eq!(
+            preflight_check(&message(&payer, 1, 0), &[&other], &[]),
+            Err(PreflightError::MissingSigners {
+                missing: vec![payer.pubkey()]
+            })
+        );
+    }
+
+    #[test]
+    fn oversized_transaction_suggests_a_lookup_table() {
+        let payer = Keypair::new();
+        let err = preflight_check(&message(&payer, 40, 0), &[&payer], &[]).unwrap_err();
+
+        let PreflightError::TooLarge {
+            size_bytes,
+            limit,
+            suggestion,
+        } = err
+        else {
+            panic!("expected TooLarge, got {:?}", err);
+        };
+        assert!(size_bytes > limit);
+        assert!(suggestion.contains("new lookup table"));
+    }
+
+    #[test]
+    fn oversized_data_cannot_be_fixed_by_a_lookup_table() {
+        let payer = Keypair::new();
+        let err =
+            preflight_check(&message(&payer, 0, PACKET_DATA_SIZE), &[&payer], &[]).unwrap_err();
+
+        let PreflightError::TooLarge { suggestion, .. } = err else {
+            panic!("expected TooLarge, got {:?}", err);
+        };
+        assert!(suggestion.contains("split the instructions"));
+    }
+
+    #[test]
+    fn account_lock_limit_counts_lookup_table_keys() {
+        let payer = Keypair::new();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: (0..MAX_TX_ACCOUNT_LOCKS)
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+        };
+        let metas = table
+            .addresses
+            .iter()
+            .map(|key| AccountMeta::new(*key, false))
+            .collect();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &[], metas);
+        let message = VersionedMessage::V0(
+            v0::Message::try_compile(
+                &payer.pubkey(),
+                &[ix],
+                std::slice::from_ref(&table),
+                Hash::default(),
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(
+            preflight_check(&message, &[&payer], &[table]),
+            Err(PreflightError::TooManyAccountLocks {
+                num_locks: MAX_TX_ACCOUNT_LOCKS + 2,
+                limit: MAX_TX_ACCOUNT_LOCKS,
+            })
+        );
+    }
+}
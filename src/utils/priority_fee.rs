@@ -0,0 +1,282 @@
+use reqwest::Client;
+use serde_json::{Value, json};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::*;
+
+pub const PRIORITY_FEE_CACHE_TTL_MS: u64 = 400;
+/// Account sets cached per source, the oldest is evicted beyond this
+pub const PRIORITY_FEE_CACHE_CAPACITY: usize = 256;
+/// `getRecentPrioritizationFees` accepts at most this many accounts per request
+pub const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+type FeeCache<T> = Mutex<HashMap<Vec<Pubkey>, (Instant, T)>>;
+
+/// Caches `value`, dropping expired entries and then the oldest beyond `PRIORITY_FEE_CACHE_CAPACITY`
+fn cache_insert<T>(cache: &FeeCache<T>, key: Vec<Pubkey>, value: T, ttl: Duration) {
+    let mut cache = cache.lock().unwrap();
+    cache.retain(|_, (at, _)| at.elapsed() < ttl);
+
+    while cache.len() >= PRIORITY_FEE_CACHE_CAPACITY {
+        let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, (at, _))| *at)
+            .map(|(key, _)| key.clone())
+        else {
+            break;
+        };
+        cache.remove(&oldest);
+    }
+    cache.insert(key, (Instant::now(), value));
+}
+
+/// Writable accounts of `ixs`, the ones whose local fee markets decide the priority fee
+pub fn writable_accounts(ixs: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = ixs
+        .iter()
+        .flat_map(|ix| ix.accounts.iter())
+        .filter(|meta| meta.is_writable)
+        .map(|meta| meta.pubkey)
+        .collect();
+    accounts.sort();
+    accounts.dedup();
+    accounts
+}
+
+/// Nearest-rank percentile (0-100) of `values`
+pub fn percentile(values: &[u64], pct: f64) -> u64 {
+    if values.is_empty() {
+        return 0;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let rank = ((pct.clamp(0.0, 100.0) / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+}
+
+/// Recommends `Tips::priority_fee_micro_lamport` from recent prioritization fees
+pub struct PriorityFeeOracle {
+    pub rpc_client: Arc<RpcClient>,
+    pub http_client: Client,
+    /// Helius RPC url with api key, enables `getPriorityFeeEstimate`
+    pub helius_rpc_url: Option<String>,
+    pub cache_ttl: Duration,
+    rpc_cache: FeeCache<Vec<u64>>,
+    helius_cache: FeeCache<Value>,
+}
+
+impl PriorityFeeOracle {
+    pub fn new(rpc_endpoint: String) -> Self {
+        Self {
+            rpc_client: Arc::new(RpcClient::new(rpc_endpoint)),
+            http_client: Client::new(),
+            helius_rpc_url: None,
+            cache_ttl: Duration::from_millis(PRIORITY_FEE_CACHE_TTL_MS),
+            rpc_cache: Mutex::new(HashMap::new()),
+            helius_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_helius(mut self, helius: &Helius) -> Self {
+        self.helius_rpc_url = Some(format!("{}{}", HELIUS_RPC_ENDPOINT, helius.auth_key));
+        self
+    }
+
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Recent prioritization fees (micro-lamports per CU) paid for `accounts`, one per slot,
+    /// cached. More than `MAX_PRIORITIZATION_FEE_ACCOUNTS` accounts are queried in chunks and
+    /// each slot keeps its highest fee, as a single request over every account would.
+    pub async fn recent_fees(&self, accounts: &[Pubkey]) -> anyhow::Result<Vec<u64>> {
+        let key = cache_key(accounts);
+
+        if let Some((at, fees)) = self.rpc_cache.lock().unwrap().get(&key)
+            && at.elapsed() < self.cache_ttl
+        {
+            return Ok(fees.clone());
+        }
+
+        let mut by_slot: BTreeMap<u64, u64> = BTreeMap::new();
+        // An empty account list is still one request, for the global fee market
+        let chunks: Vec<&[Pubkey]> = if key.is_empty() {
+            vec![&[]]
+        } else {
+            key.chunks(MAX_PRIORITIZATION_FEE_ACCOUNTS).collect()
+        };
+        for chunk in chunks {
+            for fee in self
+                .rpc_client
+                .get_recent_prioritization_fees(chunk)
+                .await?
+            {
+                let slot_fee = by_slot.entry(fee.slot).or_default();
+                *slot_fee = (*slot_fee).max(fee.prioritization_fee);
+            }
+        }
+        let fees: Vec<u64> = by_slot.into_values().collect();
+
+        cache_insert(&self.rpc_cache, key, fees.clone(), self.cache_ttl);
+        Ok(fees)
+    }
+
+    /// Helius `getPriorityFeeEstimate` for `accounts` at the level matching `pct`, cached
+    pub async fn helius_estimate(&self, accounts: &[Pubkey], pct: f64) -> anyhow::Result<u64> {
+        let url = self
+            .helius_rpc_url
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Helius is not configured"))?;
+        let key = cache_key(accounts);
+
+        let cached = self
+            .helius_cache
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|(at, _)| at.elapsed() < self.cache_ttl)
+            .map(|(_, levels)| levels.clone());
+
+        let levels = match cached {
+            Some(levels) => levels,
+            None => {
+                let account_keys: Vec<String> = key.iter().map(|k| k.to_string()).collect();
+                let payload = json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "getPriorityFeeEstimate",
+                    "params": [{
+                        "accountKeys": account_keys,
+                        "options": { "includeAllPriorityFeeLevels": true }
+                    }]
+                });
+
+                let body: Value = self
+                    .http_client
+                    .post(url)
+                    .json(&payload)
+                    .send()
+                    .await?
+                    .json()
+                    .await?;
+
+                if let Some(error) = body.get("error") {
+                    anyhow::bail!("Helius getPriorityFeeEstimate failed: {}", error);
+                }
+
+                let levels = body["result"]["priorityFeeLevels"].clone();
+                cache_insert(&self.helius_cache, key, levels.clone(), self.cache_ttl);
+                levels
+            }
+        };
+
+        // Helius levels are the 0/25/50/75/95/100th percentiles
+        let level = match pct {
+            p if p <= 0.0 => "min",
+            p if p <= 25.0 => "low",
+            p if p <= 50.0 => "medium",
+            p if p <= 75.0 => "high",
+            p if p <= 95.0 => "veryHigh",
+            _ => "unsafeMax",
+        };
+
+        levels[level]
+            .as_f64()
+            .map(|fee| fee.ceil() as u64)
+            .ok_or_else(|| anyhow::anyhow!("Helius response is missing the {} level", level))
+    }
+
+    /// Percentile-based priority fee for `accounts`, from Helius when configured,
+    /// otherwise (or on Helius failure) from `getRecentPrioritizationFees`
+    pub async fn recommend(&self, accounts: &[Pubkey], pct: f64) -> anyhow::Result<u64> {
+        if self.helius_rpc_url.is_some() {
+            match self.helius_estimate(accounts, pct).await {
+                Ok(fee) => return Ok(fee),
+                Err(err) => eprintln!("Helius priority fee estimate failed: {}", err),
+            }
+        }
+
+        let fees = self.recent_fees(accounts).await?;
+        Ok(percentile(&fees, pct))
+    }
+
+    /// Recommendation for the writable accounts of `tip_config.pure_ix`, ready for `Tips`
+    pub async fn recommend_for(&self, tip_config: &Tips, pct: f64) -> anyhow::Result<Tips> {
+        let fee = self
+            .recommend(&writable_accounts(&tip_config.pure_ix), pct)
+            .await?;
+
+        let mut tips = tip_config.clone();
+        tips.priority_fee_micro_lamport = Some(fee);
+        Ok(tips)
+    }
+}
+
+fn cache_key(accounts: &[Pubkey]) -> Vec<Pubkey> {
+    let mut key = accounts.to_vec();
+    key.sort();
+    key.dedup();
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let fees = [50, 10, 40, 20, 30];
+        assert_eq!(percentile(&fees, 0.0), 10);
+        assert_eq!(percentile(&fees, 50.0), 30);
+        assert_eq!(percentile(&fees, 75.0), 40);
+        assert_eq!(percentile(&fees, 100.0), 50);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[test]
+    fn cache_drops_expired_entries() {
+        let cache: FeeCache<u64> = Mutex::new(HashMap::new());
+        cache_insert(
+            &cache,
+            vec![Pubkey::new_unique()],
+            1,
+            Duration::from_millis(5),
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        cache_insert(
+            &cache,
+            vec![Pubkey::new_unique()],
+            2,
+            Duration::from_millis(5),
+        );
+
+        assert_eq!(cache.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cache_is_bounded() {
+        let cache: FeeCache<u64> = Mutex::new(HashMap::new());
+        let first = vec![Pubkey::new_unique()];
+        cache_insert(&cache, first.clone(), 0, Duration::from_secs(60));
+        for value in 1..=PRIORITY_FEE_CACHE_CAPACITY as u64 {
+            cache_insert(
+                &cache,
+                vec![Pubkey::new_unique()],
+                value,
+                Duration::from_secs(60),
+            );
+        }
+
+        let cache = cache.lock().unwrap();
+        assert_eq!(cache.len(), PRIORITY_FEE_CACHE_CAPACITY);
+        assert!(!cache.contains_key(&first));
+    }
+}
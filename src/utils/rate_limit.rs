@@ -0,0 +1,276 @@
+use reqwest::{Response, StatusCode, header::RETRY_AFTER};
+use std::{
+    fmt,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Backoff used when a 429 carries no usable `Retry-After`
+pub const DEFAULT_RETRY_AFTER_MS: u64 = 1_000;
+
+/// A provider answered HTTP 429
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Parsed `Retry-After`, `None` when missing or unparsable
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimited {
+    pub fn backoff(&self) -> Duration {
+        self.retry_after
+            .unwrap_or(Duration::from_millis(DEFAULT_RETRY_AFTER_MS))
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.retry_after {
+            Some(retry_after) => write!(f, "rate limited, retry after {:?}", retry_after),
+            None => write!(f, "rate limited"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Fails with `RateLimited` when the provider answered 429, call before reading the body
+pub fn check_rate_limit(response: &Response) -> Result<(), RateLimited> {
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return Ok(());
+    }
+
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, SystemTime::now()));
+    Err(RateLimited { retry_after })
+}
+
+/// Parses both `Retry-After` forms of RFC 9110 §10.2.3, delay-seconds and an HTTP-date
+/// (IMF-fixdate). A date in the past means retrying right away.
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = parse_imf_fixdate(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// `Sun, 06 Nov 1994 08:49:37 GMT`. The obsolete RFC 850 and asctime forms are not
+/// accepted, callers fall back to `DEFAULT_RETRY_AFTER_MS`.
+fn parse_imf_fixdate(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (_weekday, rest) = value.split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    let [day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    let day: u64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|name| name == month)? as u64 + 1;
+    let year: u64 = year.parse().ok()?;
+
+    let time: Vec<u64> = time
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [hour, minute, second] = time.as_slice() else {
+        return None;
+    };
+    if year < 1970 || !(1..=31).contains(&day) || *hour > 23 || *minute > 59 || *second > 60 {
+        return None;
+    }
+
+    // Days since the epoch, Howard Hinnant's days_from_civil
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y % 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// `RateLimited` behind an error returned by a client's `send_*`
+pub fn as_rate_limited(err: &anyhow::Error) -> Option<RateLimited> {
+    err.downcast_ref::<RateLimited>().copied()
+}
+
+/// Allowed request rate of one API key
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests_per_sec: f64,
+    /// Requests that may go out at once after idling
+    pub burst: u32,
+}
+
+impl RateLimit {
+    pub fn per_sec(requests_per_sec: f64) -> Self {
+        Self {
+            requests_per_sec,
+            burst: requests_per_sec.ceil().max(1.0) as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    pub limit: RateLimit,
+    tokens: f64,
+    refilled_at: Instant,
+    /// Set from a 429, no tokens are handed out before it
+    blocked_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.burst as f64,
+            refilled_at: Instant::now(),
+            blocked_until: None,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.limit.requests_per_sec).min(self.limit.burst as f64);
+        self.refilled_at = now;
+    }
+
+    /// Time until a token is available, zero if one is now
+    pub fn wait_time(&mut self) -> Duration {
+        self.refill();
+
+        let blocked = self
+            .blocked_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+        let refill = if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else if self.limit.requests_per_sec > 0.0 {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.limit.requests_per_sec)
+        } else {
+            Duration::MAX
+        };
+        blocked.max(refill)
+    }
+
+    /// Takes a token, or returns how long until one is available
+    pub fn try_acquire(&mut self) -> Result<(), Duration> {
+        let wait = self.wait_time();
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        self.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Holds every request for `backoff` and drains the bucket
+    pub fn back_off(&mut self, backoff: Duration) {
+        let until = Instant::now() + backoff;
+        self.blocked_until = Some(
+            self.blocked_until
+                .map_or(until, |blocked| blocked.max(until)),
+        );
+        self.tokens = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_delay_seconds() {
+        assert_eq!(
+            parse_retry_after(" 120 ", SystemTime::now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn parses_http_date() {
+        // 784111777 is Sun, 06 Nov 1994 08:49:37 GMT
+        let now = UNIX_EPOCH + Duration::from_secs(784_111_777 - 30);
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_imf_fixdate("Thu, 29 Feb 2024 00:00:00 GMT"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_709_164_800))
+        );
+    }
+
+    #[test]
+    fn past_date_retries_now() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_709_164_800);
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:37 GMT", now),
+            Some(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn unparsable_falls_back_to_default() {
+        let now = SystemTime::now();
+        assert_eq!(
+            parse_retry_after("Sunday, 06-Nov-94 08:49:37 GMT", now),
+            None
+        );
+        assert_eq!(parse_retry_after("Sun Nov  6 08:49:37 1994", now), None);
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(
+            RateLimited { retry_after: None }.backoff(),
+            Duration::from_millis(DEFAULT_RETRY_AFTER_MS)
+        );
+    }
+
+    #[test]
+    fn bucket_spends_burst_then_refills() {
+        let mut bucket = TokenBucket::new(RateLimit {
+            requests_per_sec: 100.0,
+            burst: 2,
+        });
+        assert!(bucket.try_acquire().is_ok());
+        assert!(bucket.try_acquire().is_ok());
+
+        let wait = bucket.try_acquire().unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= Duration::from_millis(10));
+
+        std::thread::sleep(Duration::from_millis(15));
+        assert!(bucket.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn back_off_blocks_until_retry_after() {
+        let mut bucket = TokenBucket::new(RateLimit::per_sec(1_000.0));
+        bucket.back_off(Duration::from_millis(500));
+
+        let wait = bucket.try_acquire().unwrap_err();
+        assert!(wait > Duration::from_millis(400) && wait <= Duration::from_millis(500));
+    }
+
+    #[test]
+    fn zero_rate_never_refills() {
+        let mut bucket = TokenBucket::new(RateLimit {
+            requests_per_sec: 0.0,
+            burst: 1,
+        });
+        assert!(bucket.try_acquire().is_ok());
+        assert_eq!(bucket.try_acquire(), Err(Duration::MAX));
+    }
+}
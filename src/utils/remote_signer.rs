@@ -0,0 +1,286 @@
+use serde_json::{Value, json};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::{Signer, SignerError},
+};
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+pub const REMOTE_SIGNER_TIMEOUT_MS: u64 = 2_000;
+
+/// Any signer the builders and `ultra_submit` can share across tasks
+pub type DynSigner = dyn Signer + Send + Sync;
+
+/// Where the local signing service listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteSignerEndpoint {
+    /// `host:port` of a plain HTTP service, e.g. `127.0.0.1:8899`
+    Http(String),
+    /// Unix socket path speaking the same HTTP protocol
+    Unix(PathBuf),
+}
+
+/// `Signer` backed by a signing service so keys live outside the bot process.
+///
+/// The service answers `GET /pubkey` with `{"pubkey": "<base58>"}` and
+/// `POST /sign` with `{"pubkey": "<base58>", "message": "<base64>"}` with
+/// `{"signature": "<base58>"}`. Signing blocks the calling thread for one round trip,
+/// so async callers sign on the blocking pool (`ultra_submit` does).
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    pub endpoint: RemoteSignerEndpoint,
+    pub pubkey: Pubkey,
+    pub timeout: Duration,
+}
+
+impl RemoteSigner {
+    pub fn new(endpoint: RemoteSignerEndpoint, pubkey: Pubkey) -> Self {
+        Self {
+            endpoint,
+            pubkey,
+            timeout: Duration::from_millis(REMOTE_SIGNER_TIMEOUT_MS),
+        }
+    }
+
+    /// Asks the service which key it signs with
+    pub fn connect(endpoint: RemoteSignerEndpoint) -> anyhow::Result<Self> {
+        let mut signer = Self::new(endpoint, Pubkey::default());
+        let body = signer.request("GET", "/pubkey", None)?;
+        let pubkey = body["pubkey"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Signing service response is missing pubkey"))?;
+        signer.pubkey = Pubkey::from_str(pubkey)?;
+        Ok(signer)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn request(&self, method: &str, path: &str, body: Option<Value>) -> anyhow::Result<Value> {
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        );
+
+        let response = match &self.endpoint {
+            RemoteSignerEndpoint::Http(address) => {
+                let stream = connect_tcp(address, self.timeout)?;
+                stream.set_read_timeout(Some(self.timeout))?;
+                stream.set_write_timeout(Some(self.timeout))?;
+                exchange(stream, &request)?
+            }
+            #[cfg(unix)]
+            RemoteSignerEndpoint::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(self.timeout))?;
+                stream.set_write_timeout(Some(self.timeout))?;
+                exchange(stream, &request)?
+            }
+            #[cfg(not(unix))]
+            RemoteSignerEndpoint::Unix(_) => {
+                anyhow::bail!("Unix socket signers are only supported on unix")
+            }
+        };
+
+        let (status, body) = parse_response(&response)?;
+        if status != 200 {
+            anyhow::bail!(
+                "Signing service returned status {}: {}",
+                status,
+                String::from_utf8_lossy(&body)
+            );
+        }
+
+        let body: Value = serde_json::from_slice(&body)?;
+        if let Some(error) = body.get("error") {
+            anyhow::bail!("Signing service error: {}", error);
+        }
+        Ok(body)
+    }
+}
+
+/// First address of `address` accepting a connection within `timeout`
+fn connect_tcp(address: &str, timeout: Duration) -> anyhow::Result<TcpStream> {
+    let mut last_error = None;
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    Err(match last_error {
+        Some(err) => err.into(),
+        None => anyhow::anyhow!("Signing service address {} resolved to nothing", address),
+    })
+}
+
+fn exchange<S: Read + Write>(mut stream: S, request: &str) -> anyhow::Result<Vec<u8>> {
+    stream.write_all(request.as_bytes())?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+/// Status code and body of an HTTP/1.1 response, de-chunked when sent chunked
+fn parse_response(response: &[u8]) -> anyhow::Result<(u16, Vec<u8>)> {
+    let malformed = || anyhow::anyhow!("Malformed signing service response");
+
+    let head_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&response[..head_end]).map_err(|_| malformed())?;
+    let body = &response[head_end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(malformed)?;
+
+    let mut chunked = false;
+    let mut content_length = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.to_ascii_lowercase().contains("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse::<usize>().ok();
+        }
+    }
+
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        match content_length {
+            Some(length) => body.get(..length).ok_or_else(malformed)?.to_vec(),
+            None => body.to_vec(),
+        }
+    };
+    Ok((status, body))
+}
+
+fn decode_chunked(mut body: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let malformed = || anyhow::anyhow!("Malformed chunked signing service response");
+    let mut decoded = Vec::new();
+
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(malformed)?;
+        let size_line = std::str::from_utf8(&body[..line_end]).map_err(|_| malformed())?;
+        // Chunk extensions follow a `;`
+        let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size_hex, 16).map_err(|_| malformed())?;
+        body = &body[line_end + 2..];
+
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = body.get(..size).ok_or_else(malformed)?;
+        decoded.extend_from_slice(chunk);
+        body = body
+            .get(size..)
+            .and_then(|rest| rest.strip_prefix(b"\r\n"))
+            .ok_or_else(malformed)?;
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let payload = json!({
+            "pubkey": self.pubkey.to_string(),
+            "message": bs64::encode(message),
+        });
+
+        let body = self
+            .request("POST", "/sign", Some(payload))
+            .map_err(|err| SignerError::Connection(err.to_string()))?;
+        let signature = body["signature"]
+            .as_str()
+            .ok_or_else(|| SignerError::Protocol("response is missing signature".to_string()))?;
+        let signature =
+            Signature::from_str(signature).map_err(|err| SignerError::Protocol(err.to_string()))?;
+
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Protocol(format!(
+                "signature does not verify for {}",
+                self.pubkey
+            )));
+        }
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_content_length_response() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 13\r\n\r\n{\"pubkey\":1}\n";
+        let (status, body) = parse_response(response).unwrap();
+
+        assert_eq!(status, 200);
+        assert_eq!(body, b"{\"pubkey\":1}\n");
+    }
+
+    #[test]
+    fn decodes_chunked_response() {
+        let response = b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n\
+            5\r\n{\"sig\r\n7;ext=1\r\nnature\"\r\n3\r\n:1}\r\n0\r\n\r\n";
+        let (status, body) = parse_response(response).unwrap();
+
+        assert_eq!(status, 200);
+        assert_eq!(body, b"{\"signature\":1}");
+    }
+
+    #[test]
+    fn rejects_truncated_responses() {
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 10").is_err());
+        assert!(
+            parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nA\r\nabc")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn reports_non_200_status() {
+        let response = b"HTTP/1.1 403 Forbidden\r\nContent-Length: 6\r\n\r\ndenied";
+        assert_eq!(parse_response(response).unwrap(), (403, b"denied".to_vec()));
+    }
+
+    #[test]
+    fn tcp_connect_reports_unreachable_signers() {
+        assert!(connect_tcp("127.0.0.1:1", Duration::from_millis(100)).is_err());
+        assert!(connect_tcp("not an address", Duration::from_millis(100)).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    message::AddressLookupTableAccount,
+    pubkey::Pubkey,
+    signature::Signature,
+    signer::Signer,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use std::str::FromStr;
+
+use crate::*;
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Let the RPC swap in a fresh blockhash (also allows simulating nonce txs)
+    pub replace_recent_blockhash: bool,
+    /// Verify signatures; when false the transaction is simulated unsigned
+    pub sig_verify: bool,
+    pub commitment: CommitmentConfig,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            replace_recent_blockhash: true,
+            sig_verify: false,
+            commitment: CommitmentConfig::processed(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationReturnData {
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    pub return_data: Option<SimulationReturnData>,
+    /// Blockhash used by the RPC when `replace_recent_blockhash` is set
+    pub replacement_blockhash: Option<Hash>,
+}
+
+impl SimulationResult {
+    pub fn is_ok(&self) -> bool {
+        self.err.is_none()
+    }
+}
+
+/// Simulates the exact V0 message `build_v0_*` would submit (ALTs included)
+#[allow(clippy::too_many_arguments)]
+pub async fn simulate<S: Signer + ?Sized>(
+    ixs: Vec<Instruction>,
+    fee_payer: &Pubkey,
+    signers: &[&S],
+    recent_blockhash: Hash,
+    nonce_ix: Option<Instruction>,
+    alt: &[AddressLookupTableAccount],
+    rpc_endpoint: String,
+    config: SimulationConfig,
+) -> anyhow::Result<SimulationResult> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_endpoint, config.commitment);
+
+    simulate_with_client(
+        &rpc_client,
+        ixs,
+        fee_payer,
+        signers,
+        recent_blockhash,
+        nonce_ix,
+        alt,
+        config,
+    )
+    .await
+}
+
+/// `simulate` against an existing RPC client
+#[allow(clippy::too_many_arguments)]
+pub async fn simulate_with_client<S: Signer + ?Sized>(
+    rpc_client: &RpcClient,
+    ixs: Vec<Instruction>,
+    fee_payer: &Pubkey,
+    signers: &[&S],
+    recent_blockhash: Hash,
+    nonce_ix: Option<Instruction>,
+    alt: &[AddressLookupTableAccount],
+    config: SimulationConfig,
+) -> anyhow::Result<SimulationResult> {
+    let message = compile_v0(ixs, fee_payer, recent_blockhash, nonce_ix, alt)?;
+
+    let txn = if config.sig_verify {
+        let signers = required_signers(&message, signers)?;
+        VersionedTransaction::try_new(message, &signers)?
+    } else {
+        let num_signatures = message.header().num_required_signatures as usize;
+        VersionedTransaction {
+            signatures: vec![Signature::default(); num_signatures],
+            message,
+        }
+    };
+
+    let result = rpc_client
+        .simulate_transaction_with_config(
+            &txn,
+            RpcSimulateTransactionConfig {
+                sig_verify: config.sig_verify,
+                replace_recent_blockhash: config.replace_recent_blockhash,
+                commitment: Some(config.commitment),
+                ..Default::default()
+            },
+        )
+        .await?
+        .value;
+
+    let return_data = match result.return_data {
+        Some(return_data) => Some(SimulationReturnData {
+            program_id: Pubkey::from_str(&return_data.program_id)?,
+            data: bs64::decode(return_data.data.0.as_bytes())?,
+        }),
+        None => None,
+    };
+
+    let replacement_blockhash = match result.replacement_blockhash {
+        Some(blockhash) => Some(Hash::from_str(&blockhash.blockhash)?),
+        None => None,
+    };
+
+    Ok(SimulationResult {
+        err: result.err,
+        logs: result.logs.unwrap_or_default(),
+        units_consumed: result.units_consumed,
+        return_data,
+        replacement_blockhash,
+    })
+}
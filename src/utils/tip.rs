@@ -1,11 +1,255 @@
-use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use solana_sdk::{instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+use solana_system_interface::instruction as system_instruction;
 
-#[derive(Debug, Clone)]
+use crate::{ComputeBudgetPolicy, ComputeBudgetSettings, extract_compute_budget};
+
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
+/// Where `add_tip_ix` puts the tip transfer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TipPlacement {
+    /// After compute budget and user instructions
+    #[default]
+    Last,
+    /// Before every other instruction (the nonce advance still stays at index 0)
+    First,
+    /// Left out of the transaction, build it with `tip_ix` / `Jito::build_tip_tx_bs64`.
+    /// `ultra_submit` rejects it since relayers would receive no tip.
+    Separate,
+}
+
+/// Only `payer`, `pure_ix` and the tip amount usually need setting,
+/// e.g. `Tips { payer, pure_ix, tip_sol_amount, ..Default::default() }`
+#[derive(Debug, Clone, Default)]
 pub struct Tips {
     pub tip_sol_amount: f64,
     pub tip_addr_idx: u8,
     pub cu: Option<u64>,
     pub priority_fee_micro_lamport: Option<u64>,
     pub payer: Pubkey,
+    /// Account funding the tip transfer, defaults to `payer`
+    pub tip_payer: Option<Pubkey>,
     pub pure_ix: Vec<Instruction>,
+    pub placement: TipPlacement,
+    pub heap_frame_bytes: Option<u32>,
+    pub loaded_accounts_data_size_limit: Option<u32>,
+    pub compute_budget_policy: ComputeBudgetPolicy,
+}
+
+impl Tips {
+    pub fn tip_source(&self) -> Pubkey {
+        self.tip_payer.unwrap_or(self.payer)
+    }
+
+    /// Tip actually transferred once the relayer minimum is applied
+    pub fn tip_lamports(&self, min_tip: f64) -> u64 {
+        (self.tip_sol_amount.max(min_tip) * LAMPORTS_PER_SOL as f64).round() as u64
+    }
+
+    /// Compute budget requested by `pure_ix` with the `Tips` values applied on top,
+    /// plus the non compute budget instructions of `pure_ix`
+    pub fn compute_budget(&self) -> anyhow::Result<(ComputeBudgetSettings, Vec<Instruction>)> {
+        let (mut settings, others) =
+            extract_compute_budget(&self.pure_ix, self.compute_budget_policy)?;
+        let compute_unit_limit = self
+            .cu
+            .map(|cu| {
+                u32::try_from(cu)
+                    .map_err(|_| anyhow::anyhow!("Compute unit limit {} does not fit in a u32", cu))
+            })
+            .transpose()?;
+
+        settings.merge(
+            &ComputeBudgetSettings {
+                compute_unit_limit,
+                compute_unit_price: self.priority_fee_micro_lamport,
+                heap_frame_bytes: self.heap_frame_bytes,
+                loaded_accounts_data_size_limit: self.loaded_accounts_data_size_limit,
+            },
+            self.compute_budget_policy,
+        )?;
+        settings.validate()?;
+
+        Ok((settings, others))
+    }
+
+    /// `compute_budget` that never fails: duplicates are merged whatever the policy,
+    /// out of range values clamped and unparsable compute budget ixs kept as they are
+    pub fn clamped_compute_budget(&self) -> (ComputeBudgetSettings, Vec<Instruction>) {
+        let mut settings = ComputeBudgetSettings::default();
+        let mut others = Vec::with_capacity(self.pure_ix.len());
+        for ix in &self.pure_ix {
+            match extract_compute_budget(std::slice::from_ref(ix), ComputeBudgetPolicy::Merge) {
+                Ok((found, rest)) if rest.is_empty() => {
+                    let _ = settings.merge(&found, ComputeBudgetPolicy::Merge);
+                }
+                _ => others.push(ix.clone()),
+            }
+        }
+
+        let _ = settings.merge(
+            &ComputeBudgetSettings {
+                compute_unit_limit: self.cu.map(|cu| cu.min(MAX_COMPUTE_UNIT_LIMIT) as u32),
+                compute_unit_price: self.priority_fee_micro_lamport,
+                heap_frame_bytes: self.heap_frame_bytes,
+                loaded_accounts_data_size_limit: self.loaded_accounts_data_size_limit,
+            },
+            ComputeBudgetPolicy::Merge,
+        );
+        settings.clamp();
+
+        (settings, others)
+    }
+
+    /// CU limit the runtime will charge for, falling back to the default
+    /// per-instruction limit (pure ixs + nonce advance + tip transfer)
+    pub fn compute_unit_limit(&self) -> u64 {
+        let (settings, others) = self
+            .compute_budget()
+            .unwrap_or_else(|_| self.clamped_compute_budget());
+
+        settings
+            .compute_unit_limit
+            .map(u64::from)
+            .or(self.cu)
+            .unwrap_or_else(|| {
+                (DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT * (others.len() as u64 + 2))
+                    .min(MAX_COMPUTE_UNIT_LIMIT)
+            })
+    }
+
+    pub fn compute_unit_price(&self) -> u64 {
+        self.compute_budget()
+            .unwrap_or_else(|_| self.clamped_compute_budget())
+            .0
+            .compute_unit_price
+            .unwrap_or(0)
+    }
+
+    pub fn priority_fee_lamports(&self) -> u64 {
+        let price = self.compute_unit_price();
+        let micro_lamports = (price as u128) * (self.compute_unit_limit() as u128);
+        micro_lamports.div_ceil(MICRO_LAMPORTS_PER_LAMPORT as u128) as u64
+    }
+
+    /// Tip + priority fee paid if this transaction lands
+    pub fn total_fee_lamports(&self, min_tip: f64) -> u64 {
+        self.tip_lamports(min_tip)
+            .saturating_add(self.priority_fee_lamports())
+    }
+}
+
+/// Transfer of the tip (at least `min_tip`) from the tip source to `recipient`
+pub fn tip_transfer_ix(tip_config: &Tips, recipient: &str, min_tip: f64) -> Instruction {
+    system_instruction::transfer(
+        &tip_config.tip_source(),
+        &Pubkey::from_str_const(recipient),
+        tip_config.tip_lamports(min_tip),
+    )
+}
+
+/// Compute budget ixs, `pure_ix` and the tip transfer ordered by `tip_config.placement`.
+/// Never fails, compute budget values `try_build_tip_ixs` rejects are merged and clamped.
+pub fn build_tip_ixs(tip_config: &Tips, tip_ix: Instruction) -> Vec<Instruction> {
+    let (settings, pure_ix) = tip_config
+        .compute_budget()
+        .unwrap_or_else(|_| tip_config.clamped_compute_budget());
+    order_tip_ixs(tip_config.placement, settings, pure_ix, tip_ix)
+}
+
+/// Compute budget ixs (one per kind), the rest of `pure_ix` and the tip transfer
+/// ordered by `tip_config.placement`
+pub fn try_build_tip_ixs(
+    tip_config: &Tips,
+    tip_ix: Instruction,
+) -> anyhow::Result<Vec<Instruction>> {
+    let (settings, pure_ix) = tip_config.compute_budget()?;
+    Ok(order_tip_ixs(
+        tip_config.placement,
+        settings,
+        pure_ix,
+        tip_ix,
+    ))
+}
+
+fn order_tip_ixs(
+    placement: TipPlacement,
+    settings: ComputeBudgetSettings,
+    pure_ix: Vec<Instruction>,
+    tip_ix: Instruction,
+) -> Vec<Instruction> {
+    let mut ixs = settings.to_instructions();
+    ixs.extend(pure_ix);
+
+    match placement {
+        TipPlacement::Last => ixs.push(tip_ix),
+        TipPlacement::First => ixs.insert(0, tip_ix),
+        TipPlacement::Separate => {}
+    }
+
+    ixs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+
+    #[test]
+    fn tips_values_override_pure_ix_compute_budget() {
+        let tips = Tips {
+            cu: Some(250_000),
+            pure_ix: vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(100_000),
+                ComputeBudgetInstruction::set_compute_unit_price(7),
+            ],
+            ..Default::default()
+        };
+        let (settings, others) = tips.compute_budget().unwrap();
+
+        assert_eq!(settings.compute_unit_limit, Some(250_000));
+        assert_eq!(settings.compute_unit_price, Some(7));
+        assert!(others.is_empty());
+    }
+
+    #[test]
+    fn oversized_cu_is_an_error() {
+        let tips = Tips {
+            cu: Some(u64::from(u32::MAX) + 1),
+            ..Default::default()
+        };
+        assert!(tips.compute_budget().is_err());
+    }
+
+    #[test]
+    fn build_tip_ixs_clamps_instead_of_panicking() {
+        let tip_ix = tip_transfer_ix(&Tips::default(), "11111111111111111111111111111111", 0.0);
+        let tips = Tips {
+            cu: Some(2_000_000),
+            pure_ix: vec![
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+                ComputeBudgetInstruction::request_heap_frame(1),
+            ],
+            compute_budget_policy: ComputeBudgetPolicy::Reject,
+            ..Default::default()
+        };
+        assert!(try_build_tip_ixs(&tips, tip_ix.clone()).is_err());
+
+        let ixs = build_tip_ixs(&tips, tip_ix.clone());
+        assert_eq!(
+            ixs,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT as u32),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+                ComputeBudgetInstruction::request_heap_frame(crate::MIN_HEAP_FRAME_BYTES),
+                tip_ix,
+            ]
+        );
+        assert_eq!(tips.compute_unit_limit(), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(tips.compute_unit_price(), 2);
+    }
 }